@@ -0,0 +1,189 @@
+use super::{AnimationPlayerLink, AnimationSetEntry, AnimationState};
+use crate::physics::{Character, Momentum};
+use bevy::animation::{EntityPath, Keyframes, VariableCurve};
+use bevy::prelude::*;
+
+const LAYER_BLEND_SPEED: f32 = 8.0;
+
+/// An extra clip blended over the base `AnimationPlayer` for the bones under
+/// its mask, e.g. an attack on the upper body while the legs keep running.
+#[derive(Component)]
+pub struct AnimationLayer {
+    pub state: AnimationState,
+    clip: Handle<AnimationClip>,
+    mask: Vec<EntityPath>,
+    bones: Vec<(EntityPath, Entity)>,
+    elapsed: f32,
+    speed: f32,
+    looping: bool,
+    weight: f32,
+    target_weight: f32,
+}
+
+impl AnimationLayer {
+    pub fn new(
+        state: AnimationState,
+        clip: Handle<AnimationClip>,
+        entry: &AnimationSetEntry,
+        mask: Vec<EntityPath>,
+    ) -> Self {
+        AnimationLayer {
+            state,
+            clip,
+            mask,
+            bones: Vec::new(),
+            elapsed: 0.0,
+            speed: entry.speed,
+            looping: entry.looping,
+            weight: 0.0,
+            target_weight: 1.0,
+        }
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    pub fn clip(&self) -> &Handle<AnimationClip> {
+        &self.clip
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+}
+
+pub fn drive_animation_layers(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut layer_query: Query<(Entity, &Character, &Momentum, &mut AnimationLayer)>,
+) {
+    for (entity, character, momentum, mut layer) in &mut layer_query {
+        layer.target_weight = if character.state == layer.state && momentum.is_any() {
+            1.0
+        } else {
+            0.0
+        };
+
+        let step = time.delta_seconds() * LAYER_BLEND_SPEED;
+        layer.weight = if layer.weight < layer.target_weight {
+            (layer.weight + step).min(layer.target_weight)
+        } else {
+            (layer.weight - step).max(layer.target_weight)
+        };
+        layer.elapsed += time.delta_seconds() * layer.speed;
+
+        if layer.weight == 0.0 && layer.target_weight == 0.0 {
+            commands.entity(entity).remove::<AnimationLayer>();
+        }
+    }
+}
+
+pub fn apply_animation_layers(
+    clips: Res<Assets<AnimationClip>>,
+    mut layer_query: Query<(&AnimationPlayerLink, &mut AnimationLayer)>,
+    name_query: Query<(&Name, Option<&Children>)>,
+    mut bone_query: Query<&mut Transform>,
+) {
+    for (link, mut layer) in &mut layer_query {
+        if layer.weight <= 0.0 {
+            continue;
+        }
+        let Some(clip) = clips.get(&layer.clip) else {
+            continue;
+        };
+
+        if layer.bones.is_empty() {
+            let mut bones = Vec::new();
+            collect_masked_bones(
+                link.get(),
+                &mut Vec::new(),
+                &layer.mask,
+                &name_query,
+                &mut bones,
+            );
+            layer.bones = bones;
+        }
+
+        let time = if layer.looping && clip.duration() > 0.0 {
+            layer.elapsed % clip.duration()
+        } else {
+            layer.elapsed.min(clip.duration())
+        };
+
+        for (path, bone) in &layer.bones {
+            let Some(curves) = clip.curves_for_path(path) else {
+                continue;
+            };
+            let Ok(mut transform) = bone_query.get_mut(*bone) else {
+                continue;
+            };
+
+            for curve in curves {
+                blend_curve(&mut transform, curve, time, layer.weight);
+            }
+        }
+    }
+}
+
+fn collect_masked_bones(
+    entity: Entity,
+    path: &mut Vec<Name>,
+    mask: &[EntityPath],
+    name_query: &Query<(&Name, Option<&Children>)>,
+    bones: &mut Vec<(EntityPath, Entity)>,
+) {
+    let Ok((name, children)) = name_query.get(entity) else {
+        return;
+    };
+    path.push(name.clone());
+
+    if mask.iter().any(|root| path.starts_with(&root.parts)) {
+        bones.push((
+            EntityPath {
+                parts: path.clone(),
+            },
+            entity,
+        ));
+    }
+
+    if let Some(children) = children {
+        for child in children.iter() {
+            collect_masked_bones(*child, path, mask, name_query, bones);
+        }
+    }
+    path.pop();
+}
+
+fn blend_curve(transform: &mut Transform, curve: &VariableCurve, time: f32, weight: f32) {
+    let timestamps = &curve.keyframe_timestamps;
+    if timestamps.is_empty() {
+        return;
+    }
+
+    let (step, next, lerp) = if time <= timestamps[0] {
+        (0, 0, 0.0)
+    } else if time >= timestamps[timestamps.len() - 1] {
+        (timestamps.len() - 1, timestamps.len() - 1, 0.0)
+    } else {
+        let step = timestamps.partition_point(|timestamp| *timestamp <= time) - 1;
+        let lerp = (time - timestamps[step]) / (timestamps[step + 1] - timestamps[step]);
+        (step, step + 1, lerp)
+    };
+
+    match &curve.keyframes {
+        Keyframes::Rotation(keyframes) => {
+            let rotation = keyframes[step].slerp(keyframes[next], lerp);
+            transform.rotation = transform.rotation.slerp(rotation, weight);
+        }
+        Keyframes::Translation(keyframes) => {
+            let translation = keyframes[step].lerp(keyframes[next], lerp);
+            transform.translation = transform.translation.lerp(translation, weight);
+        }
+        Keyframes::Scale(keyframes) => {
+            let scale = keyframes[step].lerp(keyframes[next], lerp);
+            transform.scale = transform.scale.lerp(scale, weight);
+        }
+        Keyframes::Weights(_) => (),
+    }
+}
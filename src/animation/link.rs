@@ -0,0 +1,245 @@
+use super::AnimationTransitionEvent;
+use crate::physics::Character;
+use crate::Animated;
+use bevy::prelude::*;
+
+/// Lives on an `Animated` character and points at the `AnimationPlayer` inside its scene.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AnimationPlayerLink(Entity);
+
+impl AnimationPlayerLink {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Lives on an `AnimationPlayer` entity and points back at the character that owns it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct AnimatedCharacterLink(Entity);
+
+impl AnimatedCharacterLink {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+pub fn link_animation_players(
+    mut commands: Commands,
+    mut transition_writer: EventWriter<AnimationTransitionEvent>,
+    player_query: Query<Entity, Added<AnimationPlayer>>,
+    parent_query: Query<&Parent>,
+    character_query: Query<&Character, With<Animated>>,
+) {
+    for player_entity in &player_query {
+        let Some(character_entity) = parent_query
+            .iter_ancestors(player_entity)
+            .find(|ancestor| character_query.contains(*ancestor))
+        else {
+            continue;
+        };
+        let Ok(character) = character_query.get(character_entity) else {
+            continue;
+        };
+
+        commands
+            .entity(character_entity)
+            .insert(AnimationPlayerLink(player_entity));
+        commands
+            .entity(player_entity)
+            .insert(AnimatedCharacterLink(character_entity));
+        transition_writer.send(AnimationTransitionEvent {
+            character_id: character.id,
+            transition: character.state.transition(),
+            parent_entity: character_entity,
+        });
+    }
+}
+
+pub fn unlink_despawned_animation_players(
+    mut commands: Commands,
+    mut removed_players: RemovedComponents<AnimationPlayer>,
+    mut removed_characters: RemovedComponents<Character>,
+    character_query: Query<(Entity, &AnimationPlayerLink)>,
+    player_query: Query<(Entity, &AnimatedCharacterLink)>,
+    animation_player_query: Query<(), With<AnimationPlayer>>,
+    live_character_query: Query<(), With<Character>>,
+) {
+    if removed_players.iter().count() > 0 {
+        for (character_entity, link) in &character_query {
+            if !animation_player_query.contains(link.get()) {
+                commands
+                    .entity(character_entity)
+                    .remove::<AnimationPlayerLink>();
+            }
+        }
+    }
+
+    if removed_characters.iter().count() > 0 {
+        for (player_entity, link) in &player_query {
+            if !live_character_query.contains(link.get()) {
+                commands
+                    .entity(player_entity)
+                    .remove::<AnimatedCharacterLink>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<AnimationTransitionEvent>().add_systems(
+            Update,
+            (unlink_despawned_animation_players, link_animation_players).chain(),
+        );
+        app
+    }
+
+    /// A character with its `AnimationPlayer` under an armature, like a glTF scene.
+    fn spawn_character(app: &mut App) -> (Entity, Entity) {
+        spawn_character_at_depth(app, 2)
+    }
+
+    /// A character with its `AnimationPlayer` `depth` levels below it.
+    fn spawn_character_at_depth(app: &mut App, depth: usize) -> (Entity, Entity) {
+        let character = app.world.spawn((Character::default(), Animated)).id();
+        let mut parent = character;
+        for _ in 1..depth {
+            parent = app.world.spawn_empty().set_parent(parent).id();
+        }
+        let player = spawn_player(app, parent);
+        (character, player)
+    }
+
+    fn spawn_player(app: &mut App, parent: Entity) -> Entity {
+        app.world
+            .spawn(AnimationPlayer::default())
+            .set_parent(parent)
+            .id()
+    }
+
+    fn linked_player(app: &App, character: Entity) -> Option<Entity> {
+        app.world
+            .get::<AnimationPlayerLink>(character)
+            .map(|link| link.get())
+    }
+
+    fn linked_character(app: &App, player: Entity) -> Option<Entity> {
+        app.world
+            .get::<AnimatedCharacterLink>(player)
+            .map(|link| link.get())
+    }
+
+    #[test]
+    fn links_a_nested_animation_player() {
+        let mut app = app();
+        let (character, player) = spawn_character(&mut app);
+
+        app.update();
+
+        let player_link = app.world.get::<AnimationPlayerLink>(character);
+        assert_eq!(player_link.map(|link| link.get()), Some(player));
+        let character_link = app.world.get::<AnimatedCharacterLink>(player);
+        assert_eq!(character_link.map(|link| link.get()), Some(character));
+        let events = app.world.resource::<Events<AnimationTransitionEvent>>();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn links_an_animation_player_at_any_depth() {
+        for depth in [1, 3, 5] {
+            let mut app = app();
+            let (character, player) = spawn_character_at_depth(&mut app, depth);
+
+            app.update();
+
+            assert_eq!(
+                linked_player(&app, character),
+                Some(player),
+                "depth {}",
+                depth
+            );
+            assert_eq!(
+                linked_character(&app, player),
+                Some(character),
+                "depth {}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_animation_players_outside_characters() {
+        let mut app = app();
+        let scenery = app.world.spawn_empty().id();
+        let player = spawn_player(&mut app, scenery);
+
+        app.update();
+
+        assert!(linked_character(&app, player).is_none());
+        let events = app.world.resource::<Events<AnimationTransitionEvent>>();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn relinks_a_replaced_animation_player() {
+        let mut app = app();
+        let (character, old_player) = spawn_character(&mut app);
+        app.update();
+
+        let armature = app.world.get::<Parent>(old_player).unwrap().get();
+        app.world.entity_mut(old_player).despawn_recursive();
+        app.update();
+        assert!(linked_player(&app, character).is_none());
+
+        let new_player = spawn_player(&mut app, armature);
+        app.update();
+
+        assert_eq!(linked_player(&app, character), Some(new_player));
+        assert_eq!(linked_character(&app, new_player), Some(character));
+    }
+
+    #[test]
+    fn relinks_an_animation_player_replaced_in_the_same_frame() {
+        let mut app = app();
+        let (character, old_player) = spawn_character(&mut app);
+        app.update();
+
+        // Swapping a character's model despawns the old scene and spawns the
+        // new one before the unlinking system sees either.
+        let armature = app.world.get::<Parent>(old_player).unwrap().get();
+        app.world.entity_mut(old_player).despawn_recursive();
+        let new_player = spawn_player(&mut app, armature);
+        app.update();
+
+        assert_eq!(linked_player(&app, character), Some(new_player));
+        assert_eq!(linked_character(&app, new_player), Some(character));
+    }
+
+    #[test]
+    fn unlinks_a_despawned_animation_player() {
+        let mut app = app();
+        let (character, player) = spawn_character(&mut app);
+        app.update();
+
+        app.world.entity_mut(player).despawn_recursive();
+        app.update();
+
+        assert!(app.world.get::<AnimationPlayerLink>(character).is_none());
+    }
+
+    #[test]
+    fn unlinks_a_despawned_character() {
+        let mut app = app();
+        let (character, player) = spawn_character(&mut app);
+        app.update();
+
+        app.world.despawn(character);
+        app.update();
+
+        assert!(app.world.get::<AnimatedCharacterLink>(player).is_none());
+    }
+}
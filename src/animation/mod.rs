@@ -1,61 +1,160 @@
-use crate::Animated;
+use crate::combat::Dead;
+use crate::physics::{Character, Grounded, Momentum};
+use crate::CharacterAssets;
 use crate::CharacterId;
 use crate::GameState;
+use bevy::gltf::Gltf;
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::transform::TransformSystem;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+use std::time::Duration;
+
+mod layer;
+mod link;
+mod notify;
+mod set;
+
+pub use layer::AnimationLayer;
+pub use link::{AnimatedCharacterLink, AnimationPlayerLink};
+pub use notify::{AnimationNotifyEvent, CurrentAnimation};
+pub use set::{AnimationMarker, AnimationNotify, AnimationSet, AnimationSetEntry};
 
 pub struct AnimationPlugin;
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(AnimationCharacterMap::default())
+        app.add_asset::<AnimationSet>()
+            .init_asset_loader::<set::AnimationSetLoader>()
             .add_event::<AnimationTransitionEvent>()
+            .add_event::<ActionAnimationEvent>()
+            .add_event::<AnimationNotifyEvent>()
+            .add_systems(
+                Update,
+                (
+                    link::unlink_despawned_animation_players,
+                    link::link_animation_players,
+                    start_action_animations,
+                    update_animation_state,
+                )
+                    .chain()
+                    .in_set(AnimationSystems::UpdateState)
+                    .run_if(in_state(GameState::Gameplay)),
+            )
             .add_systems(
                 Update,
-                store_animation_relationships.run_if(in_state(GameState::Gameplay)),
+                (
+                    play_transitions,
+                    notify::emit_animation_notifies,
+                    layer::drive_animation_layers,
+                )
+                    .chain()
+                    .in_set(AnimationSystems::Playback)
+                    .run_if(in_state(GameState::Gameplay)),
+            )
+            .add_systems(
+                PostUpdate,
+                layer::apply_animation_layers
+                    .after(bevy::animation::animation_player)
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::Gameplay)),
+            )
+            .add_systems(
+                Update,
+                apply_deferred
+                    .after(AnimationSystems::UpdateState)
+                    .before(AnimationSystems::Playback),
+            )
+            .configure_set(
+                Update,
+                AnimationSystems::Playback.after(AnimationSystems::UpdateState),
             );
     }
 }
 
-#[derive(Resource, Default)]
-pub struct AnimationCharacterMap(HashMap<Entity, Entity>);
-
-impl AnimationCharacterMap {
-    pub fn get(&self, key_entity: Entity) -> Option<Entity> {
-        self.0.get(&key_entity).copied()
-    }
-
-    pub fn insert(&mut self, key_entity: Entity, value_entity: Entity) {
-        self.0.insert(key_entity, value_entity);
-    }
-
-    pub fn len(&self) -> usize {
-        self.0.len()
-    }
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AnimationSystems {
+    UpdateState,
+    Playback,
 }
 
-#[derive(Default)]
+const RUN_THRESHOLD: f32 = 10.0;
+const VERTICAL_THRESHOLD: f32 = 0.5;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum AnimationState {
     #[default]
     Idle,
     Walk,
     Run,
-    Attacking,
+    /// Which attack of the character's set, see `AnimationTransition::ToAttack`.
+    Attacking(u8),
     Rising,
     Falling,
     Hurt,
     Knockdown,
+    Interacting,
+    PickingUp,
+    Throwing,
+    Dodging,
 }
 
+impl AnimationState {
+    pub fn is_locomotion(self) -> bool {
+        matches!(
+            self,
+            Self::Idle | Self::Walk | Self::Run | Self::Rising | Self::Falling
+        )
+    }
+
+    pub fn transition(self) -> AnimationTransition {
+        use AnimationTransition::*;
+
+        match self {
+            Self::Idle => ToIdle,
+            Self::Walk => ToWalk,
+            Self::Run => ToRun,
+            Self::Attacking(attack) => ToAttack(attack),
+            Self::Rising => ToJump,
+            Self::Falling => ToFall,
+            Self::Hurt => ToHurt,
+            Self::Knockdown => ToKnockdown,
+            Self::Interacting => ToInteract,
+            Self::PickingUp => ToPickup,
+            Self::Throwing => ToThrow,
+            Self::Dodging => ToDodge,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
 pub enum AnimationTransition {
     ToIdle,
+    ToWalk,
     ToRun,
     ToPickup,
     ToKick,
     ToInteract,
     ToJump,
+    ToFall,
     ToThrow,
+    ToDodge,
     ToAttack(u8),
+    ToHurt,
+    ToKnockdown,
+}
+
+impl AnimationTransition {
+    pub fn blend_duration(self) -> Duration {
+        use AnimationTransition::*;
+
+        match self {
+            ToIdle | ToWalk | ToRun => Duration::from_millis(200),
+            ToJump | ToFall => Duration::from_millis(120),
+            ToAttack(_) | ToKick | ToHurt | ToDodge => Duration::from_millis(80),
+            _ => Duration::from_millis(150),
+        }
+    }
 }
 
 #[derive(Event)]
@@ -65,20 +164,168 @@ pub struct AnimationTransitionEvent {
     pub parent_entity: Entity,
 }
 
-pub fn store_animation_relationships(
+/// Holds a character in a non-locomotion state until the timer runs out.
+#[derive(Component)]
+pub struct AnimationLock(Timer);
+
+impl AnimationLock {
+    pub fn new(seconds: f32) -> Self {
+        AnimationLock(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+
+    pub fn finished(&self) -> bool {
+        self.0.finished()
+    }
+}
+
+#[derive(Event)]
+pub struct ActionAnimationEvent {
+    pub entity: Entity,
+    pub state: AnimationState,
+    pub duration: f32,
+}
+
+pub fn start_action_animations(
+    mut commands: Commands,
+    mut action_reader: EventReader<ActionAnimationEvent>,
+    mut transition_writer: EventWriter<AnimationTransitionEvent>,
+    mut character_query: Query<(&mut Character, Option<&mut AnimationLock>)>,
+) {
+    for event in action_reader.iter() {
+        let Ok((mut character, lock)) = character_query.get_mut(event.entity) else {
+            continue;
+        };
+
+        character.state = event.state;
+        match lock {
+            Some(mut lock) => *lock = AnimationLock::new(event.duration),
+            None => {
+                commands
+                    .entity(event.entity)
+                    .insert(AnimationLock::new(event.duration));
+            }
+        }
+        transition_writer.send(AnimationTransitionEvent {
+            character_id: character.id,
+            transition: event.state.transition(),
+            parent_entity: event.entity,
+        });
+    }
+}
+
+/// Releases finished animation locks and follows movement with locomotion.
+/// The dead are left lying in their knockdown until they are revived.
+#[allow(clippy::type_complexity)]
+pub fn update_animation_state(
+    time: Res<Time>,
     mut commands: Commands,
-    mut animation_character_map: ResMut<AnimationCharacterMap>,
-    child_query: Query<(Entity, &Parent), Added<AnimationPlayer>>,
-    grandparent_query: Query<(Entity, &Children), With<Animated>>,
+    mut transition_writer: EventWriter<AnimationTransitionEvent>,
+    mut character_query: Query<
+        (
+            Entity,
+            &mut Character,
+            &Momentum,
+            &Velocity,
+            Option<&Grounded>,
+            Option<&mut AnimationLock>,
+        ),
+        Without<Dead>,
+    >,
 ) {
-    for (grandchild_entity, grandchild_parent) in &child_query {
-        for (grandparent_entity, grandparent_children) in &grandparent_query {
-            if grandparent_children
-                .into_iter()
-                .any(|entity| *entity == grandchild_parent.get())
-            {
-                animation_character_map.insert(grandparent_entity, grandchild_entity);
-                commands.entity(grandparent_entity).remove::<Animated>();
+    for (entity, mut character, momentum, velocity, grounded, lock) in &mut character_query {
+        if let Some(mut lock) = lock {
+            lock.0.tick(time.delta());
+            if !lock.0.finished() {
+                continue;
+            }
+            commands.entity(entity).remove::<AnimationLock>();
+        } else if !character.state.is_locomotion() {
+            continue;
+        }
+
+        let next_state = if grounded.is_none() {
+            if velocity.linvel.y > VERTICAL_THRESHOLD {
+                AnimationState::Rising
+            } else {
+                AnimationState::Falling
+            }
+        } else if !momentum.is_any() {
+            AnimationState::Idle
+        } else if momentum.get() < RUN_THRESHOLD {
+            AnimationState::Walk
+        } else {
+            AnimationState::Run
+        };
+
+        if next_state != character.state {
+            character.state = next_state;
+            transition_writer.send(AnimationTransitionEvent {
+                character_id: character.id,
+                transition: next_state.transition(),
+                parent_entity: entity,
+            });
+        }
+    }
+}
+
+fn play_transitions(
+    mut commands: Commands,
+    characters: CharacterAssets,
+    sets: Res<Assets<AnimationSet>>,
+    gltfs: Res<Assets<Gltf>>,
+    mut transition_reader: EventReader<AnimationTransitionEvent>,
+    character_query: Query<(&Character, &Momentum)>,
+    link_query: Query<&AnimationPlayerLink>,
+    mut animation_player_query: Query<&mut AnimationPlayer>,
+) {
+    for event in transition_reader.iter() {
+        let Some(set) = characters
+            .animation_set(event.character_id)
+            .and_then(|set| sets.get(set))
+        else {
+            continue;
+        };
+
+        let Some((clip, entry)) = set.clip(event.transition, &gltfs) else {
+            continue;
+        };
+
+        if let (Some(mask), Ok((character, momentum))) = (
+            entry.mask.as_ref().and_then(|name| set.mask(name)),
+            character_query.get(event.parent_entity),
+        ) {
+            if momentum.is_any() {
+                commands
+                    .entity(event.parent_entity)
+                    .insert(AnimationLayer::new(
+                        character.state,
+                        clip,
+                        entry,
+                        mask.clone(),
+                    ))
+                    .insert(CurrentAnimation::new(event.transition, 0.0));
+                continue;
+            }
+        }
+
+        if let Ok(link) = link_query.get(event.parent_entity) {
+            if let Ok(mut animation_player) = animation_player_query.get_mut(link.get()) {
+                animation_player
+                    .play_with_transition(clip, event.transition.blend_duration())
+                    .set_speed(entry.speed);
+
+                if entry.looping {
+                    animation_player.repeat();
+                } else {
+                    animation_player.stop_repeating();
+                }
+
+                commands
+                    .entity(event.parent_entity)
+                    .insert(CurrentAnimation::new(
+                        event.transition,
+                        animation_player.elapsed(),
+                    ));
             }
         }
     }
@@ -0,0 +1,146 @@
+use super::{
+    AnimationLayer, AnimationNotify, AnimationPlayerLink, AnimationSet, AnimationTransition,
+};
+use crate::physics::Character;
+use crate::{CharacterAssets, CharacterId};
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct CurrentAnimation {
+    pub transition: AnimationTransition,
+    previous_elapsed: f32,
+}
+
+impl CurrentAnimation {
+    pub fn new(transition: AnimationTransition, elapsed: f32) -> Self {
+        CurrentAnimation {
+            transition,
+            previous_elapsed: elapsed - f32::EPSILON,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct AnimationNotifyEvent {
+    pub character_id: CharacterId,
+    pub transition: AnimationTransition,
+    pub notify: AnimationNotify,
+    pub parent_entity: Entity,
+}
+
+pub fn emit_animation_notifies(
+    characters: CharacterAssets,
+    sets: Res<Assets<AnimationSet>>,
+    clips: Res<Assets<AnimationClip>>,
+    mut notify_writer: EventWriter<AnimationNotifyEvent>,
+    mut character_query: Query<(
+        Entity,
+        &Character,
+        &AnimationPlayerLink,
+        &mut CurrentAnimation,
+        Option<&AnimationLayer>,
+    )>,
+    animation_player_query: Query<&AnimationPlayer>,
+) {
+    for (entity, character, link, mut current, layer) in &mut character_query {
+        let Ok(animation_player) = animation_player_query.get(link.get()) else {
+            continue;
+        };
+        let Some(entry) = characters
+            .animation_set(character.id)
+            .and_then(|set| sets.get(set))
+            .and_then(|set| set.entry(current.transition))
+        else {
+            continue;
+        };
+        // A clip layered over the legs keeps its own time, the base player is
+        // still busy with locomotion.
+        let (clip, elapsed) = match layer {
+            Some(layer) if layer.state.transition() == current.transition => {
+                (layer.clip(), layer.elapsed())
+            }
+            _ => (
+                animation_player.animation_clip(),
+                animation_player.elapsed(),
+            ),
+        };
+        let Some(clip) = clips.get(clip) else {
+            continue;
+        };
+
+        for marker in &entry.markers {
+            if marker_crossed(
+                marker.time,
+                current.previous_elapsed,
+                elapsed,
+                clip.duration(),
+                entry.looping,
+            ) {
+                notify_writer.send(AnimationNotifyEvent {
+                    character_id: character.id,
+                    transition: current.transition,
+                    notify: marker.notify.clone(),
+                    parent_entity: entity,
+                });
+            }
+        }
+        current.previous_elapsed = elapsed;
+    }
+}
+
+/// `AnimationPlayer::elapsed` keeps counting past the end of a repeating clip,
+/// so a looping marker is crossed whenever the number of whole cycles since it
+/// changes between two samples. A sample that went backwards has wrapped
+/// around to the start of the loop.
+fn marker_crossed(time: f32, previous: f32, current: f32, duration: f32, looping: bool) -> bool {
+    if !looping || duration <= 0.0 {
+        return previous < time && time <= current;
+    }
+
+    if current < previous {
+        let (previous, current) = (previous.rem_euclid(duration), current.rem_euclid(duration));
+        return time > previous || time <= current;
+    }
+
+    ((current - time) / duration).floor() > ((previous - time) / duration).floor()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::marker_crossed;
+
+    #[test]
+    fn crosses_a_marker_between_samples() {
+        assert!(marker_crossed(0.5, 0.4, 0.6, 1.0, false));
+        assert!(marker_crossed(0.5, 0.4, 0.5, 1.0, false));
+        assert!(marker_crossed(0.5, 0.4, 0.6, 1.0, true));
+    }
+
+    #[test]
+    fn misses_a_marker_outside_the_samples() {
+        assert!(!marker_crossed(0.5, 0.1, 0.3, 1.0, false));
+        assert!(!marker_crossed(0.5, 0.5, 0.7, 1.0, false));
+        assert!(!marker_crossed(0.5, 0.6, 0.6, 1.0, true));
+        assert!(!marker_crossed(0.5, 1.1, 1.3, 1.0, true));
+    }
+
+    #[test]
+    fn crosses_a_marker_as_the_loop_wraps() {
+        // Elapsed time that keeps counting into the second cycle.
+        assert!(marker_crossed(0.1, 0.9, 1.2, 1.0, true));
+        assert!(!marker_crossed(0.5, 0.9, 1.2, 1.0, true));
+        // Elapsed time that went back to the start of the clip.
+        assert!(marker_crossed(0.1, 0.9, 0.2, 1.0, true));
+        assert!(marker_crossed(0.95, 0.9, 0.2, 1.0, true));
+        assert!(!marker_crossed(0.5, 0.9, 0.2, 1.0, true));
+    }
+
+    #[test]
+    fn clips_that_do_not_loop_stop_at_the_end() {
+        assert!(marker_crossed(1.0, 0.9, 1.0, 1.0, false));
+        // A finished clip holds its last frame, its markers don't come round again.
+        assert!(!marker_crossed(0.1, 1.0, 1.0, 1.0, false));
+        assert!(!marker_crossed(0.1, 0.9, 1.5, 1.0, false));
+        assert!(!marker_crossed(0.1, 0.9, 0.2, 1.0, false));
+    }
+}
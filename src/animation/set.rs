@@ -0,0 +1,134 @@
+use super::AnimationTransition;
+use bevy::animation::EntityPath;
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct AnimationSetDefinition {
+    source: Option<String>,
+    #[serde(default)]
+    masks: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    clips: HashMap<AnimationTransition, AnimationSetEntry>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimationSetEntry {
+    pub animation: String,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default = "default_looping")]
+    pub looping: bool,
+    #[serde(default)]
+    pub markers: Vec<AnimationMarker>,
+    #[serde(default)]
+    pub mask: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimationMarker {
+    pub time: f32,
+    pub notify: AnimationNotify,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum AnimationNotify {
+    Footstep,
+    HitboxStart,
+    HitboxEnd,
+    Release,
+    Custom(String),
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+#[derive(TypeUuid, TypePath)]
+#[uuid = "6b7c3d2e-5a0f-4f7e-9a41-0d8f3c2b1e57"]
+pub struct AnimationSet {
+    pub source: Option<Handle<Gltf>>,
+    pub masks: HashMap<String, Vec<EntityPath>>,
+    pub clips: HashMap<AnimationTransition, AnimationSetEntry>,
+}
+
+impl AnimationSet {
+    pub fn mask(&self, name: &str) -> Option<&Vec<EntityPath>> {
+        self.masks.get(name)
+    }
+
+    pub fn entry(&self, transition: AnimationTransition) -> Option<&AnimationSetEntry> {
+        self.clips.get(&transition)
+    }
+
+    pub fn clip(
+        &self,
+        transition: AnimationTransition,
+        gltfs: &Assets<Gltf>,
+    ) -> Option<(Handle<AnimationClip>, &AnimationSetEntry)> {
+        let entry = self.entry(transition)?;
+        let gltf = gltfs.get(self.source.as_ref()?)?;
+        let clip = gltf.named_animations.get(&entry.animation)?;
+
+        Some((clip.clone_weak(), entry))
+    }
+}
+
+#[derive(Default)]
+pub struct AnimationSetLoader;
+
+impl AssetLoader for AnimationSetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let definition: AnimationSetDefinition = ron::de::from_bytes(bytes)?;
+            let mut dependencies: Vec<AssetPath<'static>> = Vec::new();
+
+            let source = definition.source.map(|path| {
+                let asset_path = AssetPath::from(path.as_str()).to_owned();
+                dependencies.push(asset_path.clone());
+                load_context.get_handle(asset_path)
+            });
+
+            load_context.set_default_asset(
+                LoadedAsset::new(AnimationSet {
+                    source,
+                    masks: definition
+                        .masks
+                        .into_iter()
+                        .map(|(name, roots)| {
+                            (name, roots.iter().map(|root| bone_path(root)).collect())
+                        })
+                        .collect(),
+                    clips: definition.clips,
+                })
+                .with_dependencies(dependencies),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["animset.ron"]
+    }
+}
+
+fn bone_path(path: &str) -> EntityPath {
+    EntityPath {
+        parts: path
+            .split('/')
+            .map(|part| Name::new(part.to_owned()))
+            .collect(),
+    }
+}
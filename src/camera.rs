@@ -1,15 +1,19 @@
-use crate::GameState;
+use crate::player::Player;
+use crate::{CameraController, GameState};
 use bevy::prelude::*;
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Gameplay), spawn_camera)
+        app.add_event::<RoomTransitionEvent>()
             .add_systems(
                 Update,
-                (update_camera_position).run_if(in_state(GameState::Gameplay)),
-            );
+                (frame_players, update_camera_position)
+                    .chain()
+                    .run_if(in_state(GameState::Gameplay)),
+            )
+            .add_systems(Update, cut_to_room);
     }
 }
 
@@ -18,18 +22,65 @@ pub struct MainCamera {
     pub target_transform: Transform,
 }
 
+/// Sent when the players arrive in another room, `target_transform` being
+/// where they come in.
 #[derive(Event)]
 pub struct RoomTransitionEvent {
     pub target_transform: Transform,
 }
 
-fn spawn_camera(mut commands: Commands) {
-    commands.spawn((
-        Camera3dBundle::default(),
-        MainCamera {
-            target_transform: Transform::from_xyz(0.0, 7.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
-        },
-    ));
+const FRAMING_DISTANCE: f32 = 20.0;
+const FRAMING_SPREAD_SCALE: f32 = 1.2;
+
+/// Keeps every player in view by aiming at their centroid and backing off as they spread out.
+fn frame_players(
+    mut camera_query: Query<(&mut MainCamera, &CameraController)>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let Ok((mut camera, controller)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let positions: Vec<Vec3> = player_query
+        .iter()
+        .map(|transform| transform.translation)
+        .collect();
+    if positions.is_empty() {
+        return;
+    }
+
+    let centroid = positions.iter().sum::<Vec3>() / positions.len() as f32;
+    let spread = positions
+        .iter()
+        .map(|position| position.distance(centroid))
+        .fold(0.0, f32::max);
+
+    let rotation = Quat::from_rotation_x(-controller.x_angle.to_radians());
+    let distance = FRAMING_DISTANCE + spread * FRAMING_SPREAD_SCALE;
+
+    camera.target_transform.rotation = rotation;
+    camera.target_transform.translation =
+        centroid + controller.offset + rotation * Vec3::Z * distance;
+}
+
+/// Cuts straight to the players' arrival in a new room rather than panning across.
+fn cut_to_room(
+    mut transition_reader: EventReader<RoomTransitionEvent>,
+    mut camera_query: Query<(&mut Transform, &mut MainCamera, &CameraController)>,
+) {
+    let Some(event) = transition_reader.iter().last() else {
+        return;
+    };
+    let Ok((mut transform, mut camera, controller)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let rotation = Quat::from_rotation_x(-controller.x_angle.to_radians());
+    camera.target_transform.rotation = rotation;
+    camera.target_transform.translation = event.target_transform.translation
+        + controller.offset
+        + rotation * Vec3::Z * FRAMING_DISTANCE;
+    *transform = camera.target_transform;
 }
 
 fn update_camera_position(time: Res<Time>, mut camera_query: Query<(&mut Transform, &MainCamera)>) {
@@ -0,0 +1,240 @@
+use super::projectile::FireProjectileEvent;
+use super::{AttackEvent, DamageEvent, EquippedWeapon, Faction, Hurtbox, ProjectileSpec, Weapon};
+use crate::animation::{
+    AnimationNotify, AnimationNotifyEvent, AnimationSet, AnimationState, AnimationTransition,
+};
+use crate::physics::Character;
+use crate::{CharacterAssets, WeaponCache};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// Distance in front of the character's centre where the hitbox starts.
+const HITBOX_OFFSET: f32 = 0.4;
+const HITBOX_HEIGHT: f32 = 1.0;
+
+/// A swing in progress. The hitbox opens and closes as the attack animation
+/// crosses its `HitboxStart` and `HitboxEnd` markers, or on the weapon's
+/// `hit_window` when the animation has none. Ranged weapons fire their
+/// projectile when the window opens instead.
+#[derive(Component)]
+pub struct AttackWindow {
+    attack: u8,
+    /// Seconds into the swing to open and close at, without markers.
+    timed: Option<(f32, f32)>,
+    elapsed: f32,
+    reach: f32,
+    damage: f32,
+    knockback: f32,
+    projectile: Option<ProjectileSpec>,
+    opened: bool,
+    hitbox: Option<Entity>,
+}
+
+impl AttackWindow {
+    pub fn close(&self, commands: &mut Commands) {
+        if let Some(hitbox) = self.hitbox {
+            commands.entity(hitbox).despawn_recursive();
+        }
+    }
+
+    fn open(
+        &mut self,
+        owner: Entity,
+        commands: &mut Commands,
+        fire_writer: &mut EventWriter<FireProjectileEvent>,
+    ) {
+        if self.opened {
+            return;
+        }
+        self.opened = true;
+
+        if let Some(projectile) = self.projectile.clone() {
+            fire_writer.send(FireProjectileEvent {
+                owner,
+                projectile,
+                damage: self.damage,
+                knockback: self.knockback,
+            });
+            return;
+        }
+
+        let hitbox = commands
+            .spawn((
+                Name::from("Hitbox"),
+                Hitbox {
+                    owner,
+                    damage: self.damage,
+                    knockback: self.knockback,
+                    hit: Vec::new(),
+                },
+                Collider::cuboid(0.5, 0.6, self.reach * 0.5),
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+                TransformBundle::from_transform(Transform::from_xyz(
+                    0.0,
+                    HITBOX_HEIGHT,
+                    -(HITBOX_OFFSET + self.reach * 0.5),
+                )),
+            ))
+            .set_parent(owner)
+            .id();
+        self.hitbox = Some(hitbox);
+    }
+}
+
+/// A sensor in front of an attacking character. Each target is hit at most once per swing.
+#[derive(Component)]
+pub struct Hitbox {
+    pub owner: Entity,
+    pub damage: f32,
+    pub knockback: f32,
+    hit: Vec<Entity>,
+}
+
+pub fn start_attacks(
+    mut commands: Commands,
+    mut attack_reader: EventReader<AttackEvent>,
+    weapon_cache: Res<WeaponCache>,
+    weapons: Res<Assets<Weapon>>,
+    characters: CharacterAssets,
+    sets: Res<Assets<AnimationSet>>,
+    attacker_query: Query<(&Character, &EquippedWeapon, Option<&AttackWindow>)>,
+) {
+    for event in attack_reader.iter() {
+        let Ok((character, equipped, previous)) = attacker_query.get(event.attacker) else {
+            continue;
+        };
+        let Some(weapon) = weapons.get(weapon_cache.get(equipped.0)) else {
+            continue;
+        };
+
+        let entry = characters
+            .animation_set(character.id)
+            .and_then(|set| sets.get(set))
+            .and_then(|set| set.entry(AnimationTransition::ToAttack(event.attack)));
+        let has_marker = |notify: AnimationNotify| {
+            entry.map_or(false, |entry| {
+                entry.markers.iter().any(|marker| marker.notify == notify)
+            })
+        };
+        let timed =
+            if has_marker(AnimationNotify::HitboxStart) && has_marker(AnimationNotify::HitboxEnd) {
+                None
+            } else {
+                Some(weapon.hit_window)
+            };
+
+        if let Some(previous) = previous {
+            previous.close(&mut commands);
+        }
+        commands.entity(event.attacker).insert(AttackWindow {
+            attack: event.attack,
+            timed,
+            elapsed: 0.0,
+            reach: weapon.reach,
+            damage: weapon.damage * event.power,
+            knockback: weapon.knockback * event.power,
+            projectile: weapon.projectile.clone(),
+            opened: false,
+            hitbox: None,
+        });
+    }
+}
+
+/// Opens and closes marked attack windows as the attack animation crosses
+/// its hitbox markers.
+pub fn follow_attack_notifies(
+    mut commands: Commands,
+    mut notify_reader: EventReader<AnimationNotifyEvent>,
+    mut fire_writer: EventWriter<FireProjectileEvent>,
+    mut attacker_query: Query<&mut AttackWindow>,
+) {
+    for event in notify_reader.iter() {
+        let Ok(mut window) = attacker_query.get_mut(event.parent_entity) else {
+            continue;
+        };
+        if window.timed.is_some()
+            || event.transition != AnimationTransition::ToAttack(window.attack)
+        {
+            continue;
+        }
+        match event.notify {
+            AnimationNotify::HitboxStart => {
+                window.open(event.parent_entity, &mut commands, &mut fire_writer);
+            }
+            AnimationNotify::HitboxEnd => {
+                window.close(&mut commands);
+                commands
+                    .entity(event.parent_entity)
+                    .remove::<AttackWindow>();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Runs the timed windows, and drops any window whose swing was cut short
+/// before it could close.
+pub fn update_attack_windows(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut fire_writer: EventWriter<FireProjectileEvent>,
+    mut attacker_query: Query<(Entity, &Character, &mut AttackWindow)>,
+) {
+    for (entity, character, mut window) in &mut attacker_query {
+        window.elapsed += time.delta_seconds();
+
+        let interrupted = !matches!(character.state, AnimationState::Attacking(_));
+        let closed = window
+            .timed
+            .map_or(false, |(_, close_at)| window.elapsed >= close_at);
+        if interrupted || closed {
+            window.close(&mut commands);
+            commands.entity(entity).remove::<AttackWindow>();
+        } else if let Some((open_at, _)) = window.timed {
+            if window.elapsed >= open_at {
+                window.open(entity, &mut commands, &mut fire_writer);
+            }
+        }
+    }
+}
+
+pub fn detect_hits(
+    mut collision_reader: EventReader<CollisionEvent>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut hitbox_query: Query<&mut Hitbox>,
+    hurtbox_query: Query<(&Transform, &Faction), With<Hurtbox>>,
+) {
+    for event in collision_reader.iter() {
+        let CollisionEvent::Started(first, second, _) = event else {
+            continue;
+        };
+
+        for (hitbox_entity, target) in [(*first, *second), (*second, *first)] {
+            let Ok(mut hitbox) = hitbox_query.get_mut(hitbox_entity) else {
+                continue;
+            };
+            if target == hitbox.owner || hitbox.hit.contains(&target) {
+                continue;
+            }
+            let (Ok((target_transform, target_faction)), Ok((owner_transform, owner_faction))) =
+                (hurtbox_query.get(target), hurtbox_query.get(hitbox.owner))
+            else {
+                continue;
+            };
+            if target_faction == owner_faction {
+                continue;
+            }
+
+            hitbox.hit.push(target);
+            let mut away = target_transform.translation - owner_transform.translation;
+            away.y = 0.0;
+            damage_writer.send(DamageEvent {
+                target,
+                source: hitbox.owner,
+                amount: hitbox.damage,
+                knockback: away.normalize_or_zero() * hitbox.knockback,
+            });
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::animation::{ActionAnimationEvent, AnimationState, AnimationSystems};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+mod hitbox;
+mod projectile;
+mod weapon;
+
+pub use hitbox::{AttackWindow, Hitbox};
+pub use projectile::{FireProjectileEvent, Projectile, ProjectilePool};
+pub use weapon::{EquippedWeapon, ProjectileSpec, Weapon, WeaponId};
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Weapon>()
+            .init_asset_loader::<weapon::WeaponLoader>()
+            .register_type::<Health>()
+            .add_event::<AttackEvent>()
+            .add_event::<DamageEvent>()
+            .add_event::<FireProjectileEvent>()
+            .add_systems(Startup, projectile::spawn_projectile_pool)
+            .add_systems(
+                Update,
+                (
+                    hitbox::start_attacks,
+                    hitbox::follow_attack_notifies,
+                    hitbox::update_attack_windows,
+                    projectile::fire_projectiles,
+                    projectile::move_projectiles,
+                    hitbox::detect_hits,
+                    tick_invulnerability,
+                    apply_damage,
+                )
+                    .chain()
+                    .after(AnimationSystems::Playback)
+                    .run_if(in_state(GameState::Gameplay)),
+            );
+    }
+}
+
+const HURT_SECONDS: f32 = 0.6;
+const KNOCKDOWN_SECONDS: f32 = 1.5;
+/// How long the dead stay down before anything else happens to them.
+pub const DEATH_SECONDS: f32 = 2.5;
+/// Hits that push at least this hard knock the target down instead of staggering it.
+const KNOCKDOWN_FORCE: f32 = 12.0;
+
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct Health {
+    current: f32,
+    max: f32,
+    /// Seconds of invulnerability granted after each hit.
+    grace: f32,
+    invulnerable: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health {
+            current: max,
+            max,
+            grace: 0.0,
+            invulnerable: 0.0,
+        }
+    }
+
+    pub fn with_grace(mut self, seconds: f32) -> Self {
+        self.grace = seconds;
+        self
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+
+    pub fn make_invulnerable(&mut self, seconds: f32) {
+        self.invulnerable = self.invulnerable.max(seconds);
+    }
+
+    /// Returns whether the damage landed. The dead and the invulnerable take none.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        if self.is_dead() || self.is_invulnerable() {
+            return false;
+        }
+        self.current = (self.current - amount).max(0.0);
+        self.invulnerable = self.grace;
+        true
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    pub fn revive(&mut self) {
+        self.current = self.max;
+    }
+}
+
+/// Added to a character when their health runs out.
+#[derive(Component, Debug)]
+pub struct Dead;
+
+/// Characters on the same side don't hurt each other.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Faction {
+    Adventurers,
+    Undead,
+}
+
+/// Marks a character's own collider as something hitboxes can land on.
+#[derive(Component, Default)]
+pub struct Hurtbox;
+
+#[derive(Bundle)]
+pub struct CombatBundle {
+    pub health: Health,
+    pub faction: Faction,
+    pub weapon: EquippedWeapon,
+    pub hurtbox: Hurtbox,
+    pub active_events: ActiveEvents,
+}
+
+impl CombatBundle {
+    pub fn new(health: f32, faction: Faction, weapon: WeaponId) -> Self {
+        CombatBundle {
+            health: Health::new(health),
+            faction,
+            weapon: EquippedWeapon(weapon),
+            hurtbox: Hurtbox,
+            active_events: ActiveEvents::COLLISION_EVENTS,
+        }
+    }
+
+    /// Leaves the character invulnerable for `seconds` after each hit.
+    pub fn with_grace(mut self, seconds: f32) -> Self {
+        self.health = self.health.with_grace(seconds);
+        self
+    }
+}
+
+/// Sent when a character starts a swing; `attack` is its `AnimationState::Attacking` index.
+#[derive(Event)]
+pub struct AttackEvent {
+    pub attacker: Entity,
+    pub attack: u8,
+    pub power: f32,
+}
+
+#[derive(Event)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Entity,
+    pub amount: f32,
+    pub knockback: Vec3,
+}
+
+fn tick_invulnerability(time: Res<Time>, mut health_query: Query<&mut Health>) {
+    for mut health in &mut health_query {
+        if health.is_invulnerable() {
+            health.invulnerable = (health.invulnerable - time.delta_seconds()).max(0.0);
+        }
+    }
+}
+
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_reader: EventReader<DamageEvent>,
+    mut action_writer: EventWriter<ActionAnimationEvent>,
+    mut target_query: Query<(&mut Health, &mut ExternalImpulse, Option<&AttackWindow>)>,
+) {
+    for event in damage_reader.iter() {
+        let Ok((mut health, mut impulse, attack_window)) = target_query.get_mut(event.target)
+        else {
+            continue;
+        };
+        if !health.damage(event.amount) {
+            continue;
+        }
+        impulse.impulse += event.knockback;
+
+        // Getting hit interrupts whatever swing the target was in.
+        if let Some(attack_window) = attack_window {
+            attack_window.close(&mut commands);
+            commands.entity(event.target).remove::<AttackWindow>();
+        }
+
+        let (state, duration) = if health.is_dead() {
+            commands.entity(event.target).insert(Dead);
+            (AnimationState::Knockdown, DEATH_SECONDS)
+        } else if event.knockback.length() >= KNOCKDOWN_FORCE {
+            (AnimationState::Knockdown, KNOCKDOWN_SECONDS)
+        } else {
+            (AnimationState::Hurt, HURT_SECONDS)
+        };
+        action_writer.send(ActionAnimationEvent {
+            entity: event.target,
+            state,
+            duration,
+        });
+    }
+}
@@ -0,0 +1,284 @@
+use super::{DamageEvent, Faction, Hurtbox, ProjectileSpec};
+use crate::equipment::{find_bone, EquipmentSlot};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const POOL_SIZE: usize = 48;
+/// Where shots leave from on characters without a rigged hand.
+const FALLBACK_SOCKET: Vec3 = Vec3::new(0.0, 1.2, -0.6);
+const ORB_RADIUS: f32 = 0.2;
+/// Park inactive projectiles out of the way so they never show up in a room.
+const PARKED: Vec3 = Vec3::new(0.0, -100.0, 0.0);
+
+#[derive(Event)]
+pub struct FireProjectileEvent {
+    pub owner: Entity,
+    pub projectile: ProjectileSpec,
+    pub damage: f32,
+    pub knockback: f32,
+}
+
+/// One slot of the `ProjectilePool`, flying while `active`.
+#[derive(Component, Default)]
+pub struct Projectile {
+    active: bool,
+    owner: Option<Entity>,
+    faction: Option<Faction>,
+    velocity: Vec3,
+    gravity: f32,
+    remaining: f32,
+    damage: f32,
+    knockback: f32,
+    area: f32,
+}
+
+/// Projectile entities are spawned once and reused for every shot.
+#[derive(Resource)]
+pub struct ProjectilePool {
+    projectiles: Vec<Entity>,
+    orb_mesh: Handle<Mesh>,
+}
+
+pub fn spawn_projectile_pool(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let orb_mesh = meshes.add(
+        shape::UVSphere {
+            radius: ORB_RADIUS,
+            ..default()
+        }
+        .into(),
+    );
+    let orb_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.5, 0.3, 1.0),
+        emissive: Color::rgb(1.5, 0.8, 3.0),
+        unlit: true,
+        ..default()
+    });
+
+    let projectiles = (0..POOL_SIZE)
+        .map(|index| {
+            commands
+                .spawn((
+                    Name::from(format!("Projectile {}", index)),
+                    Projectile::default(),
+                    SceneBundle {
+                        transform: Transform::from_translation(PARKED),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    Handle::<Mesh>::default(),
+                    orb_material.clone(),
+                ))
+                .id()
+        })
+        .collect();
+
+    commands.insert_resource(ProjectilePool {
+        projectiles,
+        orb_mesh,
+    });
+}
+
+pub fn fire_projectiles(
+    mut fire_reader: EventReader<FireProjectileEvent>,
+    pool: Res<ProjectilePool>,
+    owner_query: Query<(&GlobalTransform, Option<&Faction>)>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+    mut projectile_query: Query<(
+        &mut Projectile,
+        &mut Transform,
+        &mut Visibility,
+        &mut Handle<Scene>,
+        &mut Handle<Mesh>,
+    )>,
+) {
+    for event in fire_reader.iter() {
+        let Ok((owner_transform, faction)) = owner_query.get(event.owner) else {
+            continue;
+        };
+
+        // Take a free slot, or cut short whichever shot has the least time left.
+        let Some(slot) = pool.projectiles.iter().copied().min_by(|a, b| {
+            let remaining = |entity: &Entity| {
+                projectile_query
+                    .get(*entity)
+                    .map_or(f32::MAX, |(projectile, ..)| {
+                        if projectile.active {
+                            projectile.remaining
+                        } else {
+                            f32::MIN
+                        }
+                    })
+            };
+            remaining(a).total_cmp(&remaining(b))
+        }) else {
+            continue;
+        };
+
+        let origin = find_bone(
+            event.owner,
+            EquipmentSlot::MainHand.bone(),
+            &children_query,
+            &name_query,
+        )
+        .and_then(|socket| owner_query.get(socket).ok())
+        .map(|(socket_transform, _)| socket_transform.translation())
+        .unwrap_or_else(|| owner_transform.transform_point(FALLBACK_SOCKET));
+
+        let (_, rotation, _) = owner_transform.to_scale_rotation_translation();
+        let forward = rotation * Vec3::NEG_Z;
+        let right = rotation * Vec3::X;
+        let direction = Quat::from_axis_angle(right, event.projectile.arc.to_radians()) * forward;
+
+        let Ok((mut projectile, mut transform, mut visibility, mut scene, mut mesh)) =
+            projectile_query.get_mut(slot)
+        else {
+            continue;
+        };
+
+        *projectile = Projectile {
+            active: true,
+            owner: Some(event.owner),
+            faction: faction.copied(),
+            velocity: direction * event.projectile.speed,
+            gravity: event.projectile.gravity,
+            remaining: event.projectile.lifetime,
+            damage: event.damage,
+            knockback: event.knockback,
+            area: event.projectile.area,
+        };
+        *transform = Transform::from_translation(origin).looking_to(direction, Vec3::Y);
+        *visibility = Visibility::Visible;
+
+        let (next_scene, next_mesh) = match &event.projectile.model {
+            Some(model) => (model.clone_weak(), Handle::default()),
+            None => (Handle::default(), pool.orb_mesh.clone_weak()),
+        };
+        if *scene != next_scene {
+            *scene = next_scene;
+        }
+        if *mesh != next_mesh {
+            *mesh = next_mesh;
+        }
+    }
+}
+
+/// Steps every flying projectile, ray casting along its path so fast shots
+/// can't skip through thin walls, and deals damage where it lands.
+pub fn move_projectiles(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut projectile_query: Query<(&mut Projectile, &mut Transform, &mut Visibility)>,
+    hurtbox_query: Query<(Entity, &GlobalTransform, &Faction), With<Hurtbox>>,
+) {
+    let delta = time.delta_seconds();
+
+    for (mut projectile, mut transform, mut visibility) in &mut projectile_query {
+        if !projectile.active {
+            continue;
+        }
+
+        projectile.remaining -= delta;
+        projectile.velocity.y -= projectile.gravity * delta;
+        let step = projectile.velocity * delta;
+        let distance = step.length();
+
+        let faction = projectile.faction;
+        let is_hostile = |entity: Entity| {
+            hurtbox_query
+                .get(entity)
+                .map_or(true, |(_, _, target)| Some(*target) != faction)
+        };
+        let mut filter = QueryFilter::new().exclude_sensors().predicate(&is_hostile);
+        if let Some(owner) = projectile.owner {
+            filter = filter.exclude_rigid_body(owner);
+        }
+
+        let hit = if distance > 0.0 {
+            rapier_context.cast_ray(
+                transform.translation,
+                step / distance,
+                distance,
+                true,
+                filter,
+            )
+        } else {
+            None
+        };
+
+        match hit {
+            Some((entity, toi)) => {
+                let impact = transform.translation + step / distance * toi;
+                let source = projectile.owner.unwrap_or(entity);
+
+                if projectile.area > 0.0 {
+                    for (target, target_transform, faction) in hurtbox_query
+                        .iter_many(intersecting(&rapier_context, impact, projectile.area))
+                    {
+                        if Some(*faction) == projectile.faction {
+                            continue;
+                        }
+                        let away = target_transform.translation() - impact;
+                        let falloff = 1.0 - (away.length() / projectile.area).min(1.0) * 0.5;
+                        damage_writer.send(DamageEvent {
+                            target,
+                            source,
+                            amount: projectile.damage * falloff,
+                            knockback: Vec3::new(away.x, 0.0, away.z).normalize_or_zero()
+                                * projectile.knockback
+                                * falloff,
+                        });
+                    }
+                } else if hurtbox_query.contains(entity) {
+                    let mut push = projectile.velocity;
+                    push.y = 0.0;
+                    damage_writer.send(DamageEvent {
+                        target: entity,
+                        source,
+                        amount: projectile.damage,
+                        knockback: push.normalize_or_zero() * projectile.knockback,
+                    });
+                }
+
+                park(&mut projectile, &mut transform, &mut visibility);
+            }
+            None if projectile.remaining <= 0.0 => {
+                park(&mut projectile, &mut transform, &mut visibility);
+            }
+            None => {
+                transform.translation += step;
+                let direction = projectile.velocity.normalize_or_zero();
+                if direction != Vec3::ZERO {
+                    transform.look_to(direction, Vec3::Y);
+                }
+            }
+        }
+    }
+}
+
+fn intersecting(rapier_context: &RapierContext, center: Vec3, radius: f32) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    rapier_context.intersections_with_shape(
+        center,
+        Quat::IDENTITY,
+        &Collider::ball(radius),
+        QueryFilter::new().exclude_sensors(),
+        |entity| {
+            entities.push(entity);
+            true
+        },
+    );
+    entities
+}
+
+fn park(projectile: &mut Projectile, transform: &mut Transform, visibility: &mut Visibility) {
+    projectile.active = false;
+    projectile.owner = None;
+    transform.translation = PARKED;
+    *visibility = Visibility::Hidden;
+}
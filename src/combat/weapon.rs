@@ -0,0 +1,114 @@
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum WeaponId {
+    #[default]
+    Sword1Handed,
+    Axe2Handed,
+    Dagger,
+    Crossbow1Handed,
+    Crossbow2Handed,
+    Wand,
+    Staff,
+}
+
+/// The weapon a character attacks with.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct EquippedWeapon(pub WeaponId);
+
+#[derive(Deserialize)]
+struct WeaponDefinition {
+    model: String,
+    reach: f32,
+    damage: f32,
+    knockback: f32,
+    hit_window: (f32, f32),
+    #[serde(default)]
+    projectile: Option<ProjectileDefinition>,
+}
+
+#[derive(Deserialize)]
+struct ProjectileDefinition {
+    model: Option<String>,
+    speed: f32,
+    gravity: f32,
+    arc: f32,
+    lifetime: f32,
+    area: f32,
+}
+
+/// What a ranged weapon shoots. Projectiles without a model are drawn as a glowing orb.
+#[derive(Clone, Debug)]
+pub struct ProjectileSpec {
+    pub model: Option<Handle<Scene>>,
+    pub speed: f32,
+    pub gravity: f32,
+    /// Degrees above the shooter's forward direction to launch at.
+    pub arc: f32,
+    pub lifetime: f32,
+    /// Radius of the blast on impact, 0 for a single target.
+    pub area: f32,
+}
+
+#[derive(TypeUuid, TypePath, Debug)]
+#[uuid = "d4a1f7b2-3c9e-4e68-8f15-6a2b0c7e9d31"]
+pub struct Weapon {
+    pub model: Handle<Scene>,
+    pub reach: f32,
+    pub damage: f32,
+    pub knockback: f32,
+    /// Seconds into the attack that the hitbox opens and closes.
+    pub hit_window: (f32, f32),
+    pub projectile: Option<ProjectileSpec>,
+}
+
+#[derive(Default)]
+pub struct WeaponLoader;
+
+impl AssetLoader for WeaponLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let definition: WeaponDefinition = ron::de::from_bytes(bytes)?;
+            let mut dependencies: Vec<AssetPath<'static>> = Vec::new();
+            let mut load_scene = |path: &str| -> Handle<Scene> {
+                let asset_path = AssetPath::from(path).to_owned();
+                dependencies.push(asset_path.clone());
+                load_context.get_handle(asset_path)
+            };
+
+            let model = load_scene(&definition.model);
+            let projectile = definition.projectile.map(|projectile| ProjectileSpec {
+                model: projectile.model.as_deref().map(&mut load_scene),
+                speed: projectile.speed,
+                gravity: projectile.gravity,
+                arc: projectile.arc,
+                lifetime: projectile.lifetime,
+                area: projectile.area,
+            });
+
+            load_context.set_default_asset(
+                LoadedAsset::new(Weapon {
+                    model,
+                    reach: definition.reach,
+                    damage: definition.damage,
+                    knockback: definition.knockback,
+                    hit_window: definition.hit_window,
+                    projectile,
+                })
+                .with_dependencies(dependencies),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["weapon.ron"]
+    }
+}
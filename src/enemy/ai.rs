@@ -0,0 +1,267 @@
+use super::Enemy;
+use crate::animation::{ActionAnimationEvent, AnimationState};
+use crate::combat::{AttackEvent, Dead};
+use crate::navigation::NavAgent;
+use crate::physics::Direction;
+use crate::player::Player;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const EYE_HEIGHT: f32 = 1.2;
+const ARRIVE_DISTANCE: f32 = 0.6;
+const IDLE_SECONDS: f32 = 2.0;
+const ATTACK_SECONDS: f32 = 1.0;
+const ATTACK_COOLDOWN: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AiState {
+    #[default]
+    Idle,
+    Patrol,
+    Chase,
+    Attack,
+}
+
+/// What an enemy is doing, where it walks when it has nothing to do, and who it is after.
+#[derive(Component)]
+pub struct EnemyAi {
+    pub state: AiState,
+    pub target: Option<Entity>,
+    patrol: Vec<Vec3>,
+    next_point: usize,
+    last_seen: Option<Vec3>,
+    timer: Timer,
+    cooldown: Timer,
+}
+
+impl EnemyAi {
+    pub fn new(patrol: Vec<Vec3>) -> Self {
+        let mut cooldown = Timer::from_seconds(ATTACK_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+
+        EnemyAi {
+            state: AiState::Idle,
+            target: None,
+            patrol,
+            next_point: 0,
+            last_seen: None,
+            timer: Timer::from_seconds(IDLE_SECONDS, TimerMode::Once),
+            cooldown,
+        }
+    }
+
+    fn enter(&mut self, state: AiState) {
+        self.state = state;
+        match state {
+            AiState::Idle => self.timer = Timer::from_seconds(IDLE_SECONDS, TimerMode::Once),
+            AiState::Attack => self.timer = Timer::from_seconds(ATTACK_SECONDS, TimerMode::Once),
+            AiState::Patrol | AiState::Chase => (),
+        }
+    }
+}
+
+/// How far an enemy sees, how wide its view is, and how close it fights.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Perception {
+    pub sight_range: f32,
+    /// Cosine of half the view cone.
+    pub view_cone: f32,
+    /// Players this close are noticed whichever way the enemy faces.
+    pub hearing_range: f32,
+    pub attack_range: f32,
+    pub visible: Option<Entity>,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Perception {
+            sight_range: 14.0,
+            view_cone: 60.0_f32.to_radians().cos(),
+            hearing_range: 3.0,
+            attack_range: 1.8,
+            visible: None,
+        }
+    }
+}
+
+fn flat(vector: Vec3) -> Vec3 {
+    Vec3::new(vector.x, 0.0, vector.z)
+}
+
+/// Picks the closest player the enemy can see, with walls blocking the view.
+pub fn perceive_players(
+    rapier_context: Res<RapierContext>,
+    mut enemy_query: Query<(Entity, &Transform, &mut Perception), With<Enemy>>,
+    player_query: Query<(Entity, &Transform), (With<Player>, Without<Dead>)>,
+) {
+    for (entity, transform, mut perception) in &mut enemy_query {
+        let eye = transform.translation + Vec3::Y * EYE_HEIGHT;
+        let facing = flat(transform.forward()).normalize_or_zero();
+
+        perception.visible = player_query
+            .iter()
+            .filter_map(|(player, player_transform)| {
+                let offset = player_transform.translation + Vec3::Y * EYE_HEIGHT - eye;
+                let distance = offset.length();
+                if distance > perception.sight_range || distance <= f32::EPSILON {
+                    return None;
+                }
+
+                let in_view = distance <= perception.hearing_range
+                    || facing.dot(flat(offset).normalize_or_zero()) >= perception.view_cone;
+                if !in_view {
+                    return None;
+                }
+
+                let filter = QueryFilter::new()
+                    .exclude_sensors()
+                    .exclude_rigid_body(entity);
+                match rapier_context.cast_ray(eye, offset / distance, distance, true, filter) {
+                    Some((hit, _)) if hit == player => Some((player, distance)),
+                    _ => None,
+                }
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(player, _)| player);
+    }
+}
+
+pub fn update_ai_state(
+    time: Res<Time>,
+    mut action_writer: EventWriter<ActionAnimationEvent>,
+    mut attack_writer: EventWriter<AttackEvent>,
+    mut enemy_query: Query<
+        (Entity, &mut Transform, &Perception, &mut EnemyAi),
+        (With<Enemy>, Without<Dead>),
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Enemy>)>,
+) {
+    for (entity, mut transform, perception, mut ai) in &mut enemy_query {
+        ai.timer.tick(time.delta());
+        ai.cooldown.tick(time.delta());
+
+        let target_position = perception
+            .visible
+            .and_then(|player| player_query.get(player).ok())
+            .map(|player_transform| player_transform.translation);
+
+        if let Some(position) = target_position {
+            ai.target = perception.visible;
+            ai.last_seen = Some(position);
+        }
+
+        match ai.state {
+            AiState::Idle => {
+                if target_position.is_some() {
+                    ai.enter(AiState::Chase);
+                } else if ai.timer.finished() && !ai.patrol.is_empty() {
+                    ai.enter(AiState::Patrol);
+                }
+            }
+            AiState::Patrol => {
+                if target_position.is_some() {
+                    ai.enter(AiState::Chase);
+                } else if let Some(point) = ai.patrol.get(ai.next_point).copied() {
+                    if flat(point - transform.translation).length() <= ARRIVE_DISTANCE {
+                        ai.next_point = (ai.next_point + 1) % ai.patrol.len();
+                        ai.enter(AiState::Idle);
+                    }
+                }
+            }
+            AiState::Chase => match (target_position, ai.last_seen) {
+                (Some(position), _) => {
+                    let distance = flat(position - transform.translation).length();
+                    if distance <= perception.attack_range && ai.cooldown.finished() {
+                        transform.look_at(
+                            Vec3::new(position.x, transform.translation.y, position.z),
+                            Vec3::Y,
+                        );
+                        ai.enter(AiState::Attack);
+                        ai.cooldown.reset();
+                        action_writer.send(ActionAnimationEvent {
+                            entity,
+                            state: AnimationState::Attacking(0),
+                            duration: ATTACK_SECONDS,
+                        });
+                        attack_writer.send(AttackEvent {
+                            attacker: entity,
+                            attack: 0,
+                            power: 1.0,
+                        });
+                    }
+                }
+                (None, Some(position)) => {
+                    // Lost sight, check where the player was last seen before giving up.
+                    if flat(position - transform.translation).length() <= ARRIVE_DISTANCE {
+                        ai.last_seen = None;
+                        ai.target = None;
+                        ai.enter(AiState::Idle);
+                    }
+                }
+                (None, None) => {
+                    ai.target = None;
+                    ai.enter(AiState::Idle);
+                }
+            },
+            AiState::Attack => {
+                if ai.timer.finished() {
+                    ai.enter(AiState::Chase);
+                }
+            }
+        }
+    }
+}
+
+/// Routes the enemy towards whatever its state is walking to and points its
+/// `Direction` at the next waypoint, leaving the lateral movement systems to
+/// turn and move it like a player.
+pub fn steer_enemies(
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &Perception,
+            &EnemyAi,
+            &mut NavAgent,
+            &mut Direction,
+            Option<&Dead>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for (transform, perception, ai, mut agent, mut direction, dead) in &mut enemy_query {
+        if dead.is_some() {
+            direction.clear();
+            continue;
+        }
+        let goal = match ai.state {
+            AiState::Idle | AiState::Attack => None,
+            AiState::Patrol => ai.patrol.get(ai.next_point).copied(),
+            AiState::Chase => ai.last_seen,
+        };
+        if agent.goal != goal {
+            agent.goal = goal;
+        }
+
+        let Some(goal) = goal else {
+            if direction.is_any() {
+                direction.clear();
+            }
+            continue;
+        };
+
+        let stop_distance = if ai.state == AiState::Chase && perception.visible.is_some() {
+            perception.attack_range * 0.9
+        } else {
+            ARRIVE_DISTANCE * 0.9
+        };
+        if flat(goal - transform.translation).length() <= stop_distance {
+            if direction.is_any() {
+                direction.clear();
+            }
+            continue;
+        }
+
+        let waypoint = agent.next_waypoint().unwrap_or(goal);
+        direction.set(flat(waypoint - transform.translation).normalize_or_zero());
+    }
+}
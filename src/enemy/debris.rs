@@ -0,0 +1,137 @@
+use super::Enemy;
+use crate::combat::Dead;
+use crate::interaction::find_part;
+use crate::physics::Character;
+use crate::CharacterAssets;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+/// How long a dead enemy goes down before it falls apart, and how long the
+/// pieces stay on the floor.
+const BREAK_SECONDS: f32 = 0.4;
+const DEBRIS_SECONDS: f32 = 12.0;
+const SCATTER_IMPULSE: f32 = 1.5;
+/// The parts that break off a skeleton, and which way they fly.
+const LOOSE_PARTS: [(&str, Vec3); 3] = [
+    ("armLeft_broken", Vec3::X),
+    ("armRight_broken", Vec3::NEG_X),
+    ("head_broken", Vec3::Y),
+];
+
+/// What is left of a dead enemy, cleared away after a while.
+#[derive(Component)]
+pub struct Debris {
+    timer: Timer,
+}
+
+impl Debris {
+    fn new() -> Self {
+        Debris {
+            timer: Timer::from_seconds(DEBRIS_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+/// Debris whose loose parts haven't been knocked off yet, which waits on its scene.
+#[derive(Component)]
+pub struct Unscattered;
+
+/// A dead enemy about to be swapped for its broken model.
+#[derive(Component)]
+pub struct Breaking {
+    timer: Timer,
+}
+
+pub fn start_breaking(
+    mut commands: Commands,
+    enemy_query: Query<Entity, (With<Enemy>, Added<Dead>)>,
+) {
+    for entity in &enemy_query {
+        commands.entity(entity).insert(Breaking {
+            timer: Timer::from_seconds(BREAK_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+/// Swaps dead enemies for the broken version of their model.
+pub fn break_dead_enemies(
+    mut commands: Commands,
+    time: Res<Time>,
+    characters: CharacterAssets,
+    mut enemy_query: Query<(Entity, &Character, &Transform, &mut Breaking)>,
+) {
+    for (entity, character, transform, mut breaking) in &mut enemy_query {
+        if !breaking.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        let Some(scene) = characters.broken_scene(character.id) else {
+            continue;
+        };
+
+        commands.spawn((
+            Name::from(format!("{:?} Debris", character.id)),
+            Debris::new(),
+            Unscattered,
+            SceneBundle {
+                scene: scene.clone_weak(),
+                transform: *transform,
+                ..default()
+            },
+            RigidBody::Dynamic,
+            Collider::compound(vec![(
+                Vec3::Y * 0.3,
+                Quat::IDENTITY,
+                Collider::cuboid(0.3, 0.3, 0.2),
+            )]),
+        ));
+    }
+}
+
+/// Knocks the arms and head off fresh debris as loose bodies of their own.
+pub fn scatter_debris(
+    mut commands: Commands,
+    debris_query: Query<(Entity, &Transform), With<Unscattered>>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+) {
+    for (entity, transform) in &debris_query {
+        let parts: Vec<(Entity, Vec3)> = LOOSE_PARTS
+            .iter()
+            .filter_map(|(suffix, direction)| {
+                let part = find_part(entity, suffix, &children_query, &name_query)?;
+                Some((part, *direction))
+            })
+            .collect();
+        // The scene hasn't spawned yet.
+        if parts.is_empty() {
+            continue;
+        }
+
+        commands.entity(entity).remove::<Unscattered>();
+        for (part, direction) in parts {
+            let fling = transform.rotation * direction + Vec3::Y;
+            commands.entity(part).remove_parent_in_place().insert((
+                Debris::new(),
+                RigidBody::Dynamic,
+                Collider::ball(0.2),
+                ExternalImpulse {
+                    impulse: fling.normalize() * SCATTER_IMPULSE,
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+pub fn clear_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris_query: Query<(Entity, &mut Debris)>,
+) {
+    for (entity, mut debris) in &mut debris_query {
+        if debris.timer.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
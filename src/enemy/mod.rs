@@ -0,0 +1,115 @@
+use crate::combat::{CombatBundle, Faction, WeaponId};
+use crate::equipment::{EquipmentBundle, EquipmentItem, EquipmentSlot, Loadout};
+use crate::navigation::{update_nav_paths, NavAgent};
+use crate::physics::{Character, Grounded, MovementBundle, Speed};
+use crate::{CharacterAssets, CharacterId, GameState};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+mod ai;
+mod debris;
+
+pub use ai::{AiState, EnemyAi, Perception};
+pub use debris::Debris;
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (ai::perceive_players, ai::update_ai_state, ai::steer_enemies)
+                .chain()
+                .after(update_nav_paths)
+                .run_if(in_state(GameState::Gameplay)),
+        )
+        .add_systems(
+            Update,
+            (
+                debris::start_breaking,
+                debris::break_dead_enemies,
+                debris::scatter_debris,
+                debris::clear_debris,
+            )
+                .chain()
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Enemy;
+
+/// Movement and senses for each kind of skeleton.
+pub struct EnemyStats {
+    pub speed: Speed,
+    pub perception: Perception,
+    pub health: f32,
+    pub weapon: WeaponId,
+}
+
+impl EnemyStats {
+    pub fn for_character(character_id: CharacterId) -> Self {
+        use WeaponId::*;
+
+        let (base_speed, top_speed, sight_range, attack_range, health, weapon) = match character_id
+        {
+            CharacterId::SkeletonWarrior => (3.0, 7.0, 14.0, 1.8, 60.0, Axe2Handed),
+            CharacterId::SkeletonMinion => (4.0, 9.0, 10.0, 1.4, 30.0, Dagger),
+            CharacterId::SkeletonArcher => (3.0, 6.0, 20.0, 12.0, 40.0, Crossbow2Handed),
+            CharacterId::SkeletonMage => (2.5, 5.0, 18.0, 10.0, 40.0, Staff),
+            CharacterId::Adventurer(_) => (7.5, 15.0, 0.0, 0.0, 100.0, Sword1Handed),
+        };
+
+        EnemyStats {
+            speed: Speed::new(base_speed, top_speed),
+            perception: Perception {
+                sight_range,
+                attack_range,
+                ..default()
+            },
+            health,
+            weapon,
+        }
+    }
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    characters: &CharacterAssets,
+    character_id: CharacterId,
+    translation: Vec3,
+    patrol: Vec<Vec3>,
+) -> Entity {
+    let stats = EnemyStats::for_character(character_id);
+
+    commands
+        .spawn((
+            Name::from(format!("{:?}", character_id)),
+            Enemy,
+            EnemyAi::new(patrol),
+            NavAgent::default(),
+            stats.perception,
+            CombatBundle::new(stats.health, Faction::Undead, stats.weapon),
+            EquipmentBundle::new(
+                Loadout::default()
+                    .with(EquipmentSlot::MainHand, EquipmentItem::Weapon(stats.weapon)),
+            ),
+            MovementBundle {
+                collider: Collider::capsule(Vec3::Y * 0.5, Vec3::Y * 1.5, 0.5),
+                speed: stats.speed,
+                character: Character::new(character_id),
+                ..default()
+            },
+            SceneBundle {
+                scene: characters
+                    .scene(character_id)
+                    .map(|scene| scene.clone_weak())
+                    .unwrap_or_default(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Grounded,
+        ))
+        .id()
+}
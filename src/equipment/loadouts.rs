@@ -0,0 +1,57 @@
+use super::Loadout;
+use crate::player::{ClassId, Player, PlayerClass};
+use crate::ron_config;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+const LOADOUTS_PATH: &str = "config/loadouts.ron";
+
+/// The last gear equipped on each class, kept between sessions.
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Loadouts {
+    pub classes: HashMap<ClassId, Loadout>,
+}
+
+impl Loadouts {
+    pub fn load() -> Self {
+        ron_config::load(LOADOUTS_PATH)
+    }
+
+    pub fn save(&self) {
+        ron_config::save(LOADOUTS_PATH, self);
+    }
+}
+
+/// Players start with their class's equipment unless they changed it last time.
+pub fn restore_player_loadouts(
+    loadouts: Res<Loadouts>,
+    mut player_query: Query<(&PlayerClass, &mut Loadout), Added<Player>>,
+) {
+    for (class, mut loadout) in &mut player_query {
+        if let Some(saved) = loadouts.classes.get(&class.0) {
+            *loadout = saved.clone();
+        }
+    }
+}
+
+pub fn save_player_loadouts(
+    mut loadouts: ResMut<Loadouts>,
+    player_query: Query<(&PlayerClass, Ref<Loadout>), (With<Player>, Changed<Loadout>)>,
+) {
+    let mut changed = false;
+    for (class, loadout) in &player_query {
+        // A freshly spawned player only has their starting gear, nothing to remember yet.
+        if loadout.is_added() {
+            continue;
+        }
+        if loadouts.classes.get(&class.0) != Some(&*loadout) {
+            loadouts.classes.insert(class.0, loadout.clone());
+            changed = true;
+        }
+    }
+
+    if changed {
+        loadouts.save();
+    }
+}
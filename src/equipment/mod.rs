@@ -0,0 +1,311 @@
+use crate::combat::{EquippedWeapon, Weapon, WeaponId};
+use crate::{GameState, WeaponCache};
+use bevy::prelude::*;
+use bevy::scene::{SceneInstance, SceneSpawner};
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+mod loadouts;
+
+pub use loadouts::Loadouts;
+
+pub struct EquipmentPlugin;
+
+impl Plugin for EquipmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Loadouts::load())
+            .add_event::<EquipItemEvent>()
+            .add_systems(
+                Update,
+                (
+                    equip_items,
+                    loadouts::restore_player_loadouts,
+                    sync_equipped_weapon,
+                    attach_equipment,
+                    loadouts::save_player_loadouts,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Gameplay)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Back,
+}
+
+impl EquipmentSlot {
+    /// The bone in a character's rig the slot's item attaches to.
+    pub fn bone(self) -> &'static str {
+        match self {
+            EquipmentSlot::MainHand => "handslot.r",
+            EquipmentSlot::OffHand => "handslot.l",
+            EquipmentSlot::Back => "chest",
+        }
+    }
+
+    /// Where the item hangs off characters whose model has no rig to attach to.
+    fn fallback(self) -> Transform {
+        match self {
+            EquipmentSlot::MainHand => Transform::from_xyz(-0.45, 0.9, -0.2),
+            EquipmentSlot::OffHand => Transform::from_xyz(0.45, 0.9, -0.2),
+            EquipmentSlot::Back => Transform::from_xyz(0.0, 1.2, 0.3)
+                .with_rotation(Quat::from_rotation_z(45.0_f32.to_radians())),
+        }
+    }
+
+    /// Gear carried on the back sits behind the chest bone rather than in it.
+    fn bone_offset(self) -> Transform {
+        match self {
+            EquipmentSlot::Back => Transform::from_xyz(0.0, 0.1, 0.25)
+                .with_rotation(Quat::from_rotation_z(45.0_f32.to_radians())),
+            EquipmentSlot::MainHand | EquipmentSlot::OffHand => Transform::IDENTITY,
+        }
+    }
+}
+
+/// Something that can be held or worn. `Model` names a file in `characters/equipment`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum EquipmentItem {
+    Weapon(WeaponId),
+    Model(String),
+}
+
+/// The items a character wants in each slot. Change it to swap gear; the
+/// attached scenes follow on their own.
+#[derive(Component, Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Loadout {
+    pub slots: HashMap<EquipmentSlot, EquipmentItem>,
+}
+
+impl Loadout {
+    pub fn with(mut self, slot: EquipmentSlot, item: EquipmentItem) -> Self {
+        self.slots.insert(slot, item);
+        self
+    }
+
+    pub fn weapon(&self) -> Option<WeaponId> {
+        match self.slots.get(&EquipmentSlot::MainHand) {
+            Some(EquipmentItem::Weapon(weapon)) => Some(*weapon),
+            _ => None,
+        }
+    }
+}
+
+/// The scenes currently attached for a character's `Loadout`.
+#[derive(Component, Default)]
+pub struct EquipmentAttachments {
+    attached: HashMap<EquipmentSlot, (EquipmentItem, Entity)>,
+}
+
+#[derive(Bundle, Default)]
+pub struct EquipmentBundle {
+    pub loadout: Loadout,
+    pub attachments: EquipmentAttachments,
+}
+
+impl EquipmentBundle {
+    pub fn new(loadout: Loadout) -> Self {
+        EquipmentBundle {
+            loadout,
+            attachments: EquipmentAttachments::default(),
+        }
+    }
+}
+
+/// Puts `item` in `slot`, or empties the slot when `item` is `None`.
+#[derive(Event)]
+pub struct EquipItemEvent {
+    pub entity: Entity,
+    pub slot: EquipmentSlot,
+    pub item: Option<EquipmentItem>,
+}
+
+fn equip_items(
+    mut equip_reader: EventReader<EquipItemEvent>,
+    mut loadout_query: Query<&mut Loadout>,
+) {
+    for event in equip_reader.iter() {
+        let Ok(mut loadout) = loadout_query.get_mut(event.entity) else {
+            continue;
+        };
+        match &event.item {
+            Some(item) => {
+                loadout.slots.insert(event.slot, item.clone());
+            }
+            None => {
+                loadout.slots.remove(&event.slot);
+            }
+        }
+    }
+}
+
+fn sync_equipped_weapon(
+    mut weapon_query: Query<(&Loadout, &mut EquippedWeapon), Changed<Loadout>>,
+) {
+    for (loadout, mut equipped) in &mut weapon_query {
+        let weapon = loadout.weapon().unwrap_or_default();
+        if equipped.0 != weapon {
+            equipped.0 = weapon;
+        }
+    }
+}
+
+/// Finds a bone, or any other node, of a character's spawned scene by name.
+pub(crate) fn find_bone(
+    entity: Entity,
+    bone: &str,
+    children_query: &Query<&Children>,
+    name_query: &Query<&Name>,
+) -> Option<Entity> {
+    for child in children_query.get(entity).ok()?.iter() {
+        if name_query
+            .get(*child)
+            .map_or(false, |name| name.as_str() == bone)
+        {
+            return Some(*child);
+        }
+        if let Some(found) = find_bone(*child, bone, children_query, name_query) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Brings attached scenes in line with each `Loadout`. Like linking animation
+/// players, this waits until the character's scene has been spawned so the
+/// bones exist before anything is parented to them.
+fn attach_equipment(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    scene_spawner: Res<SceneSpawner>,
+    weapon_cache: Res<WeaponCache>,
+    weapons: Res<Assets<Weapon>>,
+    mut character_query: Query<(
+        Entity,
+        &Loadout,
+        &mut EquipmentAttachments,
+        Option<&SceneInstance>,
+    )>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+) {
+    for (entity, loadout, mut attachments, instance) in &mut character_query {
+        let stale: Vec<EquipmentSlot> = attachments
+            .attached
+            .iter()
+            .filter(|(slot, (item, _))| loadout.slots.get(slot) != Some(item))
+            .map(|(slot, _)| *slot)
+            .collect();
+        for slot in stale {
+            if let Some((_, attached)) = attachments.attached.remove(&slot) {
+                commands.entity(attached).despawn_recursive();
+            }
+        }
+
+        if attachments.attached.len() == loadout.slots.len() {
+            continue;
+        }
+        let Some(instance) = instance else {
+            continue;
+        };
+        if !scene_spawner.instance_is_ready(**instance) {
+            continue;
+        }
+
+        for (slot, item) in &loadout.slots {
+            if attachments.attached.contains_key(slot) {
+                continue;
+            }
+
+            let scene = match item {
+                EquipmentItem::Weapon(weapon) => weapons
+                    .get(weapon_cache.get(*weapon))
+                    .map(|weapon| weapon.model.clone_weak()),
+                EquipmentItem::Model(name) => {
+                    Some(asset_server.load(format!("characters/equipment/{}.gltf#Scene0", name)))
+                }
+            };
+            let Some(scene) = scene else {
+                continue;
+            };
+
+            let (parent, transform) =
+                match find_bone(entity, slot.bone(), &children_query, &name_query) {
+                    Some(bone) => (bone, slot.bone_offset()),
+                    None => (entity, slot.fallback()),
+                };
+            let attached = commands
+                .spawn((
+                    Name::from(format!("{:?}", slot)),
+                    SceneBundle {
+                        scene,
+                        transform,
+                        ..default()
+                    },
+                ))
+                .set_parent(parent)
+                .id();
+            attachments.attached.insert(*slot, (item.clone(), attached));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equip(app: &mut App, entity: Entity, item: Option<EquipmentItem>) {
+        app.world
+            .resource_mut::<Events<EquipItemEvent>>()
+            .send(EquipItemEvent {
+                entity,
+                slot: EquipmentSlot::MainHand,
+                item,
+            });
+        app.update();
+    }
+
+    #[test]
+    fn equipping_and_unequipping_a_weapon_updates_the_equipped_weapon() {
+        let mut app = App::new();
+        app.add_event::<EquipItemEvent>()
+            .add_systems(Update, (equip_items, sync_equipped_weapon).chain());
+        let character = app
+            .world
+            .spawn((
+                Loadout::default().with(
+                    EquipmentSlot::MainHand,
+                    EquipmentItem::Weapon(WeaponId::Staff),
+                ),
+                EquippedWeapon(WeaponId::Staff),
+            ))
+            .id();
+
+        equip(
+            &mut app,
+            character,
+            Some(EquipmentItem::Weapon(WeaponId::Dagger)),
+        );
+        let character_ref = app.world.entity(character);
+        assert_eq!(
+            character_ref.get::<Loadout>().unwrap().weapon(),
+            Some(WeaponId::Dagger)
+        );
+        assert_eq!(
+            character_ref.get::<EquippedWeapon>().unwrap().0,
+            WeaponId::Dagger
+        );
+
+        equip(&mut app, character, None);
+        let character_ref = app.world.entity(character);
+        assert!(character_ref.get::<Loadout>().unwrap().slots.is_empty());
+        assert_eq!(
+            character_ref.get::<EquippedWeapon>().unwrap().0,
+            EquippedWeapon::default().0
+        );
+    }
+}
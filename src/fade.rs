@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+pub struct FadePlugin;
+
+impl Plugin for FadePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenFade>()
+            .add_systems(Startup, spawn_fade_overlay)
+            .add_systems(Update, update_fade_overlay);
+    }
+}
+
+/// Fraction of a full fade covered per second.
+const FADE_SPEED: f32 = 2.5;
+
+/// A black overlay over the whole screen. Anything can fade it out or in and
+/// wait for it to get there.
+#[derive(Resource, Debug)]
+pub struct ScreenFade {
+    alpha: f32,
+    target: f32,
+}
+
+impl Default for ScreenFade {
+    fn default() -> Self {
+        ScreenFade {
+            alpha: 0.0,
+            target: 0.0,
+        }
+    }
+}
+
+impl ScreenFade {
+    pub fn fade_out(&mut self) {
+        self.target = 1.0;
+    }
+
+    pub fn fade_in(&mut self) {
+        self.target = 0.0;
+    }
+
+    /// Faded all the way to black.
+    pub fn is_black(&self) -> bool {
+        self.alpha >= 1.0
+    }
+
+    /// Faded all the way back in.
+    pub fn is_clear(&self) -> bool {
+        self.alpha <= 0.0
+    }
+}
+
+#[derive(Component)]
+struct FadeOverlay;
+
+fn spawn_fade_overlay(mut commands: Commands) {
+    commands.spawn((
+        Name::from("Screen Fade"),
+        FadeOverlay,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.0).into(),
+            focus_policy: FocusPolicy::Pass,
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+    ));
+}
+
+fn update_fade_overlay(
+    time: Res<Time>,
+    mut fade: ResMut<ScreenFade>,
+    mut overlay_query: Query<&mut BackgroundColor, With<FadeOverlay>>,
+) {
+    if fade.alpha == fade.target {
+        return;
+    }
+    let step = FADE_SPEED * time.delta_seconds();
+    fade.alpha = if fade.alpha < fade.target {
+        (fade.alpha + step).min(fade.target)
+    } else {
+        (fade.alpha - step).max(fade.target)
+    };
+
+    for mut color in &mut overlay_query {
+        color.0.set_a(fade.alpha);
+    }
+}
@@ -0,0 +1,243 @@
+use super::{InputDevices, PlayerAction};
+use crate::ron_config;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use leafwing_input_manager::{prelude::*, user_input::InputKind};
+use serde::{Deserialize, Serialize};
+
+const BINDINGS_PATH: &str = "config/bindings.ron";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    fn input_kind(self) -> InputKind {
+        match self {
+            Binding::Key(key) => InputKind::Keyboard(key),
+            Binding::Mouse(button) => InputKind::Mouse(button),
+            Binding::Gamepad(button) => InputKind::GamepadButton(button),
+        }
+    }
+
+    fn is_gamepad(self) -> bool {
+        matches!(self, Binding::Gamepad(_))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RebindTarget {
+    Action(PlayerAction),
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MoveKeys {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+}
+
+impl Default for MoveKeys {
+    fn default() -> Self {
+        MoveKeys {
+            up: KeyCode::W,
+            down: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct InputBindings {
+    pub actions: HashMap<PlayerAction, Vec<Binding>>,
+    pub move_keys: MoveKeys,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use PlayerAction::*;
+
+        InputBindings {
+            actions: HashMap::from_iter([
+                (
+                    Jump,
+                    vec![
+                        Binding::Key(KeyCode::Space),
+                        Binding::Gamepad(GamepadButtonType::South),
+                    ],
+                ),
+                (
+                    Attack,
+                    vec![
+                        Binding::Mouse(MouseButton::Left),
+                        Binding::Gamepad(GamepadButtonType::West),
+                    ],
+                ),
+                (
+                    HeavyAttack,
+                    vec![
+                        Binding::Mouse(MouseButton::Right),
+                        Binding::Gamepad(GamepadButtonType::RightTrigger2),
+                    ],
+                ),
+                (
+                    Interact,
+                    vec![
+                        Binding::Key(KeyCode::E),
+                        Binding::Gamepad(GamepadButtonType::North),
+                    ],
+                ),
+                (
+                    Dodge,
+                    vec![
+                        Binding::Key(KeyCode::LShift),
+                        Binding::Gamepad(GamepadButtonType::East),
+                    ],
+                ),
+                (
+                    Pause,
+                    vec![
+                        Binding::Key(KeyCode::Escape),
+                        Binding::Gamepad(GamepadButtonType::Start),
+                    ],
+                ),
+                (
+                    UseItem,
+                    vec![
+                        Binding::Key(KeyCode::Q),
+                        Binding::Gamepad(GamepadButtonType::RightTrigger),
+                    ],
+                ),
+            ]),
+            move_keys: MoveKeys::default(),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn load() -> Self {
+        ron_config::load(BINDINGS_PATH)
+    }
+
+    pub fn save(&self) {
+        ron_config::save(BINDINGS_PATH, self);
+    }
+
+    pub fn input_map(&self, devices: &InputDevices) -> InputMap<PlayerAction> {
+        let mut input_map = InputMap::default();
+
+        // Without a gamepad of its own, leafwing would read gamepad bindings from
+        // every pad, including ones other players are about to join with.
+        let listens = |binding: &Binding| {
+            if binding.is_gamepad() {
+                devices.gamepad.is_some()
+            } else {
+                devices.keyboard
+            }
+        };
+        for (action, bindings) in &self.actions {
+            for binding in bindings.iter().filter(|binding| listens(binding)) {
+                input_map.insert(binding.input_kind(), *action);
+            }
+        }
+
+        if let Some(gamepad) = devices.gamepad {
+            input_map.insert(DualAxis::left_stick(), PlayerAction::Move);
+            input_map.set_gamepad(gamepad);
+        }
+        if devices.keyboard {
+            input_map.insert(
+                VirtualDPad {
+                    up: InputKind::Keyboard(self.move_keys.up),
+                    down: InputKind::Keyboard(self.move_keys.down),
+                    left: InputKind::Keyboard(self.move_keys.left),
+                    right: InputKind::Keyboard(self.move_keys.right),
+                },
+                PlayerAction::Move,
+            );
+        }
+
+        input_map
+    }
+
+    /// A short name for the first button bound to `action`, for on-screen prompts.
+    pub fn label(&self, action: PlayerAction, gamepad: bool) -> Option<String> {
+        self.actions
+            .get(&action)?
+            .iter()
+            .find(|binding| binding.is_gamepad() == gamepad)
+            .map(|binding| match binding {
+                Binding::Key(key) => format!("{:?}", key),
+                Binding::Mouse(button) => format!("{:?} Mouse", button),
+                Binding::Gamepad(button) => format!("{:?}", button),
+            })
+    }
+
+    pub fn bound_to(&self, binding: Binding) -> Option<RebindTarget> {
+        let move_targets = [
+            (self.move_keys.up, RebindTarget::MoveUp),
+            (self.move_keys.down, RebindTarget::MoveDown),
+            (self.move_keys.left, RebindTarget::MoveLeft),
+            (self.move_keys.right, RebindTarget::MoveRight),
+        ];
+
+        if let Binding::Key(key) = binding {
+            if let Some((_, target)) = move_targets.iter().find(|(bound, _)| *bound == key) {
+                return Some(*target);
+            }
+        }
+
+        self.actions
+            .iter()
+            .find(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| RebindTarget::Action(*action))
+    }
+
+    /// Binds `binding` to `target`, taking it away from whatever held it before.
+    /// An action keeps one keyboard/mouse and one gamepad input; movement only accepts keys.
+    pub fn bind(&mut self, target: RebindTarget, binding: Binding) -> Result<(), RebindError> {
+        let is_key = matches!(binding, Binding::Key(_));
+        if !is_key && !matches!(target, RebindTarget::Action(_)) {
+            return Err(RebindError::MovementNeedsKey);
+        }
+
+        let previous = self.bound_to(binding);
+        if let Some(RebindTarget::Action(action)) = previous {
+            if let Some(bindings) = self.actions.get_mut(&action) {
+                bindings.retain(|bound| *bound != binding);
+            }
+        } else if previous.is_some() && previous != Some(target) {
+            return Err(RebindError::MovementKeyTaken);
+        }
+
+        match (target, binding) {
+            (RebindTarget::Action(action), _) => {
+                let bindings = self.actions.entry(action).or_default();
+                bindings.retain(|bound| bound.is_gamepad() != binding.is_gamepad());
+                bindings.push(binding);
+            }
+            (RebindTarget::MoveUp, Binding::Key(key)) => self.move_keys.up = key,
+            (RebindTarget::MoveDown, Binding::Key(key)) => self.move_keys.down = key,
+            (RebindTarget::MoveLeft, Binding::Key(key)) => self.move_keys.left = key,
+            (RebindTarget::MoveRight, Binding::Key(key)) => self.move_keys.right = key,
+            _ => return Err(RebindError::MovementNeedsKey),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebindError {
+    MovementKeyTaken,
+    MovementNeedsKey,
+}
@@ -0,0 +1,157 @@
+use super::PlayerAction;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Presses older than this are dropped, whatever window a reader asks for.
+const MAX_BUFFERED: Duration = Duration::from_millis(1000);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BufferedPress {
+    pub action: PlayerAction,
+    pub time: Duration,
+}
+
+/// Recent presses, oldest first, so gameplay can act on an input that came a
+/// little before the character was able to respond to it.
+#[derive(Component, Default, Debug)]
+pub struct InputBuffer {
+    presses: VecDeque<BufferedPress>,
+}
+
+impl InputBuffer {
+    pub fn push(&mut self, action: PlayerAction, time: Duration) {
+        self.presses.push_back(BufferedPress { action, time });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BufferedPress> {
+        self.presses.iter()
+    }
+
+    /// Removes the oldest press of `action` made within `window` of `now`.
+    pub fn consume(&mut self, action: PlayerAction, now: Duration, window: Duration) -> bool {
+        self.consume_any(&[action], now, window).is_some()
+    }
+
+    /// Removes and returns the oldest press of any of `actions` made within `window` of `now`.
+    pub fn consume_any(
+        &mut self,
+        actions: &[PlayerAction],
+        now: Duration,
+        window: Duration,
+    ) -> Option<PlayerAction> {
+        let index = self.presses.iter().position(|press| {
+            actions.contains(&press.action) && now.saturating_sub(press.time) <= window
+        })?;
+        self.presses.remove(index).map(|press| press.action)
+    }
+
+    pub fn clear(&mut self) {
+        self.presses.clear();
+    }
+
+    fn prune(&mut self, now: Duration) {
+        while let Some(press) = self.presses.front() {
+            if now.saturating_sub(press.time) <= MAX_BUFFERED {
+                break;
+            }
+            self.presses.pop_front();
+        }
+    }
+}
+
+pub fn buffer_player_actions(
+    time: Res<Time>,
+    mut buffer_query: Query<(&ActionState<PlayerAction>, &mut InputBuffer)>,
+) {
+    let now = time.elapsed();
+    for (action_state, mut buffer) in &mut buffer_query {
+        buffer.prune(now);
+        for action in action_state.get_just_pressed() {
+            // Pausing doesn't wait on the player, see `pause_game`.
+            if !matches!(action, PlayerAction::Move | PlayerAction::Pause) {
+                buffer.push(action, now);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn buffered(buffer: &InputBuffer) -> Vec<PlayerAction> {
+        buffer.iter().map(|press| press.action).collect()
+    }
+
+    #[test]
+    fn presses_are_consumed_within_their_window() {
+        let mut buffer = InputBuffer::default();
+        buffer.push(PlayerAction::Jump, ms(1000));
+
+        assert!(!buffer.consume(PlayerAction::Dodge, ms(1100), ms(250)));
+        assert!(!buffer.consume(PlayerAction::Jump, ms(1300), ms(250)));
+        assert!(buffer.consume(PlayerAction::Jump, ms(1250), ms(250)));
+        // Each press is only acted on once.
+        assert!(!buffer.consume(PlayerAction::Jump, ms(1250), ms(250)));
+    }
+
+    #[test]
+    fn the_oldest_matching_press_goes_first() {
+        let mut buffer = InputBuffer::default();
+        buffer.push(PlayerAction::Attack, ms(0));
+        buffer.push(PlayerAction::Jump, ms(100));
+        buffer.push(PlayerAction::HeavyAttack, ms(200));
+        buffer.push(PlayerAction::Attack, ms(300));
+
+        let attacks = [PlayerAction::Attack, PlayerAction::HeavyAttack];
+        // The first attack is too old, but stays buffered for a wider window.
+        assert_eq!(
+            buffer.consume_any(&attacks, ms(400), ms(250)),
+            Some(PlayerAction::HeavyAttack)
+        );
+        assert_eq!(
+            buffered(&buffer),
+            vec![
+                PlayerAction::Attack,
+                PlayerAction::Jump,
+                PlayerAction::Attack
+            ]
+        );
+        assert_eq!(
+            buffer.consume_any(&attacks, ms(400), ms(500)),
+            Some(PlayerAction::Attack)
+        );
+        assert_eq!(
+            buffered(&buffer),
+            vec![PlayerAction::Jump, PlayerAction::Attack]
+        );
+        assert_eq!(
+            buffer.consume_any(&[PlayerAction::Dodge], ms(400), ms(500)),
+            None
+        );
+    }
+
+    #[test]
+    fn old_presses_are_pruned() {
+        let mut buffer = InputBuffer::default();
+        buffer.push(PlayerAction::Attack, ms(0));
+        buffer.push(PlayerAction::Jump, ms(500));
+
+        buffer.prune(MAX_BUFFERED);
+        assert_eq!(
+            buffered(&buffer),
+            vec![PlayerAction::Attack, PlayerAction::Jump]
+        );
+        buffer.prune(MAX_BUFFERED + ms(1));
+        assert_eq!(buffered(&buffer), vec![PlayerAction::Jump]);
+
+        buffer.clear();
+        assert!(buffered(&buffer).is_empty());
+    }
+}
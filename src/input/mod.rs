@@ -0,0 +1,85 @@
+use crate::GameState;
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod bindings;
+mod buffer;
+mod rebind;
+mod replay;
+
+pub use bindings::{Binding, InputBindings, RebindError, RebindTarget};
+pub use buffer::{buffer_player_actions, BufferedPress, InputBuffer};
+pub use rebind::{BindingConflictEvent, RebindRequest, Rebinding};
+pub use replay::{live_input, ReplayMode};
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            InputManagerPlugin::<PlayerAction>::default(),
+            replay::ReplayPlugin,
+        ))
+        .insert_resource(InputBindings::load())
+        .init_resource::<Rebinding>()
+        .add_event::<RebindRequest>()
+        .add_event::<BindingConflictEvent>()
+        .add_systems(
+            Update,
+            (
+                rebind::capture_rebinding,
+                rebind::start_rebinding,
+                rebind::apply_bindings,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            buffer_player_actions.run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+#[derive(
+    Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Default, Reflect, Serialize, Deserialize,
+)]
+pub enum PlayerAction {
+    #[default]
+    Jump,
+    Move,
+    Attack,
+    HeavyAttack,
+    Interact,
+    Dodge,
+    Pause,
+    UseItem,
+}
+
+/// Which devices feed a player's `InputMap`. Only the first player listens to the keyboard,
+/// and a player without a gamepad ignores every pad.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct InputDevices {
+    pub keyboard: bool,
+    pub gamepad: Option<Gamepad>,
+}
+
+#[derive(Bundle)]
+pub struct InputListenerBundle {
+    input_manager: InputManagerBundle<PlayerAction>,
+    devices: InputDevices,
+    buffer: InputBuffer,
+}
+
+impl InputListenerBundle {
+    pub fn input_map(bindings: &InputBindings, devices: InputDevices) -> InputListenerBundle {
+        InputListenerBundle {
+            input_manager: InputManagerBundle {
+                input_map: bindings.input_map(&devices),
+                ..Default::default()
+            },
+            devices,
+            buffer: InputBuffer::default(),
+        }
+    }
+}
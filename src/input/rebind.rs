@@ -0,0 +1,101 @@
+use super::bindings::{Binding, InputBindings, RebindTarget};
+use super::{InputDevices, PlayerAction};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+#[derive(Event)]
+pub struct RebindRequest {
+    pub target: RebindTarget,
+}
+
+#[derive(Event)]
+pub struct BindingConflictEvent {
+    pub binding: Binding,
+    pub previous: RebindTarget,
+    pub target: RebindTarget,
+}
+
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    target: Option<RebindTarget>,
+}
+
+impl Rebinding {
+    pub fn target(&self) -> Option<RebindTarget> {
+        self.target
+    }
+}
+
+pub fn start_rebinding(
+    mut rebinding: ResMut<Rebinding>,
+    mut request_reader: EventReader<RebindRequest>,
+) {
+    if let Some(request) = request_reader.iter().last() {
+        rebinding.target = Some(request.target);
+    }
+}
+
+pub fn capture_rebinding(
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<InputBindings>,
+    mut conflict_writer: EventWriter<BindingConflictEvent>,
+) {
+    let Some(target) = rebinding.target else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        rebinding.target = None;
+        return;
+    }
+
+    let Some(binding) = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.button_type))
+        })
+    else {
+        return;
+    };
+
+    let previous = bindings.bound_to(binding);
+    match bindings.bind(target, binding) {
+        Ok(()) => {
+            if let Some(previous) = previous.filter(|previous| *previous != target) {
+                conflict_writer.send(BindingConflictEvent {
+                    binding,
+                    previous,
+                    target,
+                });
+            }
+            bindings.save();
+            rebinding.target = None;
+        }
+        Err(error) => warn!("Could not bind {:?} to {:?}: {:?}", binding, target, error),
+    }
+}
+
+pub fn apply_bindings(
+    bindings: Res<InputBindings>,
+    mut input_map_query: Query<(Ref<InputDevices>, &mut InputMap<PlayerAction>)>,
+) {
+    for (devices, mut input_map) in &mut input_map_query {
+        if bindings.is_changed() || devices.is_changed() {
+            *input_map = bindings.input_map(&devices);
+        }
+    }
+}
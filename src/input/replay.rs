@@ -0,0 +1,653 @@
+use super::{InputBindings, InputDevices, PlayerAction};
+use crate::player::{
+    spawn_player_character, ClassId, Player, PlayerClass, PlayerSlot, SelectedClass,
+};
+use crate::{CharacterAssets, GameState};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::HashMap;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::axislike::DualAxisData;
+use leafwing_input_manager::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const MAGIC: &[u8; 4] = b"SOAR";
+const VERSION: u8 = 2;
+const TICK_RATE: u16 = 60;
+const JOINED: u8 = 0x80;
+const AXIS_SCALE: f32 = i16::MAX as f32;
+const POSITION_TOLERANCE: f32 = 0.05;
+
+/// Buttons stored as one bit each, `Move` is stored separately as an axis pair.
+const BUTTONS: [PlayerAction; 7] = [
+    PlayerAction::Jump,
+    PlayerAction::Attack,
+    PlayerAction::Interact,
+    PlayerAction::Dodge,
+    PlayerAction::Pause,
+    PlayerAction::UseItem,
+    PlayerAction::HeavyAttack,
+];
+
+/// Picked from the command line: `--record <file>`, `--replay <file>` or
+/// `--replay-check <file>`, which replays without a window and exits with the
+/// result of comparing final player positions against the recording.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub enum ReplayMode {
+    #[default]
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+    Check(PathBuf),
+}
+
+impl ReplayMode {
+    pub fn from_args() -> Self {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mode = match arg.as_str() {
+                "--record" => ReplayMode::Record,
+                "--replay" => ReplayMode::Replay,
+                "--replay-check" => ReplayMode::Check,
+                _ => continue,
+            };
+            match args.next() {
+                Some(path) => return mode(PathBuf::from(path)),
+                None => warn!("{} needs a file path, running live", arg),
+            }
+        }
+        ReplayMode::Live
+    }
+
+    pub fn is_headless(&self) -> bool {
+        matches!(self, ReplayMode::Check(_))
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self, ReplayMode::Replay(_) | ReplayMode::Check(_))
+    }
+
+    pub fn tick() -> Duration {
+        Duration::from_secs_f64(1.0 / TICK_RATE as f64)
+    }
+}
+
+/// Run condition for systems that read devices directly, such as joining.
+pub fn live_input(mode: Res<ReplayMode>) -> bool {
+    !mode.is_replaying()
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let mode = app
+            .world
+            .get_resource::<ReplayMode>()
+            .cloned()
+            .unwrap_or_default();
+
+        if mode == ReplayMode::Live {
+            return;
+        }
+
+        // Every frame advances exactly one fixed tick so physics and input line
+        // up the same way when recording and when replaying.
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(ReplayMode::tick()))
+            .insert_resource(FixedTime::new(ReplayMode::tick()))
+            .add_systems(Startup, fix_physics_timestep);
+
+        match &mode {
+            ReplayMode::Record(_) => {
+                app.init_resource::<InputRecording>()
+                    .add_systems(
+                        FixedUpdate,
+                        record_player_actions.run_if(in_state(GameState::Gameplay)),
+                    )
+                    .add_systems(Last, save_recording);
+            }
+            ReplayMode::Replay(path) | ReplayMode::Check(path) => {
+                let recording = match InputRecording::read(path) {
+                    Ok(recording) => recording,
+                    Err(error) => {
+                        error!("Could not read replay {}: {}", path.display(), error);
+                        app.add_systems(Startup, exit_app);
+                        return;
+                    }
+                };
+                app.insert_resource(recording)
+                    .init_resource::<ReplayCursor>()
+                    .add_systems(OnEnter(GameState::MainMenu), skip_main_menu)
+                    .add_systems(
+                        FixedUpdate,
+                        (detach_live_input, replay_player_actions)
+                            .chain()
+                            .run_if(in_state(GameState::Gameplay)),
+                    );
+                if mode.is_headless() {
+                    app.add_systems(
+                        Update,
+                        check_replay_positions.run_if(in_state(GameState::Gameplay)),
+                    );
+                }
+            }
+            ReplayMode::Live => (),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct PlayerFrame {
+    slot: u8,
+    buttons: u8,
+    axis: (i16, i16),
+    /// Where and as which class the player joined, on their first frame only.
+    joined: Option<(Vec3, ClassId)>,
+}
+
+impl PlayerFrame {
+    fn capture(slot: u8, action_state: &ActionState<PlayerAction>) -> Self {
+        let mut buttons = 0;
+        for (bit, action) in BUTTONS.iter().enumerate() {
+            if action_state.pressed(*action) {
+                buttons |= 1 << bit;
+            }
+        }
+        let axis = action_state
+            .axis_pair(PlayerAction::Move)
+            .map(|pair| (quantize(pair.x()), quantize(pair.y())))
+            .unwrap_or_default();
+
+        PlayerFrame {
+            slot,
+            buttons,
+            axis,
+            joined: None,
+        }
+    }
+
+    fn apply(&self, action_state: &mut ActionState<PlayerAction>) {
+        for (bit, action) in BUTTONS.iter().enumerate() {
+            let pressed = self.buttons & (1 << bit) != 0;
+            if pressed && !action_state.pressed(*action) {
+                action_state.press(*action);
+            } else if !pressed && action_state.pressed(*action) {
+                action_state.release(*action);
+            }
+        }
+
+        let (x, y) = (
+            self.axis.0 as f32 / AXIS_SCALE,
+            self.axis.1 as f32 / AXIS_SCALE,
+        );
+        if self.axis == (0, 0) {
+            action_state.release(PlayerAction::Move);
+        } else if !action_state.pressed(PlayerAction::Move) {
+            action_state.press(PlayerAction::Move);
+        }
+        action_state.action_data_mut(PlayerAction::Move).axis_pair = Some(DualAxisData::new(x, y));
+    }
+}
+
+fn quantize(value: f32) -> i16 {
+    (value.clamp(-1.0, 1.0) * AXIS_SCALE).round() as i16
+}
+
+/// Per-tick player input plus the positions players ended up at, stored as
+/// `SOAR`, version, tick rate, frames and a footer of final positions. Version 2
+/// added the class each player joined as.
+#[derive(Resource, Default, PartialEq, Debug)]
+pub struct InputRecording {
+    frames: Vec<Vec<PlayerFrame>>,
+    final_positions: Vec<(u8, Vec3)>,
+}
+
+impl InputRecording {
+    fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, self.encode())
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|error| error.to_string())?;
+        InputRecording::decode(&bytes)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&TICK_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        for frame in &self.frames {
+            bytes.push(frame.len() as u8);
+            for player in frame {
+                let joined = if player.joined.is_some() { JOINED } else { 0 };
+                bytes.push(player.slot | joined);
+                bytes.push(player.buttons);
+                bytes.extend_from_slice(&player.axis.0.to_le_bytes());
+                bytes.extend_from_slice(&player.axis.1.to_le_bytes());
+                if let Some((position, class_id)) = player.joined {
+                    write_vec3(&mut bytes, position);
+                    bytes.push(class_id.index());
+                }
+            }
+        }
+
+        bytes.push(self.final_positions.len() as u8);
+        for (slot, position) in &self.final_positions {
+            bytes.push(*slot);
+            write_vec3(&mut bytes, *position);
+        }
+
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader { bytes, at: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err("not a replay file".to_string());
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(format!("unsupported replay version {}", version));
+        }
+        let tick_rate = u16::from_le_bytes(reader.array()?);
+        if tick_rate != TICK_RATE {
+            return Err(format!("recorded at {} ticks per second", tick_rate));
+        }
+
+        let frame_count = u32::from_le_bytes(reader.array()?);
+        let mut frames = Vec::with_capacity(frame_count as usize);
+        for _ in 0..frame_count {
+            let player_count = reader.u8()?;
+            let mut frame = Vec::with_capacity(player_count as usize);
+            for _ in 0..player_count {
+                let slot = reader.u8()?;
+                let buttons = reader.u8()?;
+                let axis = (
+                    i16::from_le_bytes(reader.array()?),
+                    i16::from_le_bytes(reader.array()?),
+                );
+                let joined = if slot & JOINED != 0 {
+                    let position = reader.vec3()?;
+                    let class_index = reader.u8()?;
+                    let class_id = ClassId::from_index(class_index)
+                        .ok_or_else(|| format!("unknown class {}", class_index))?;
+                    Some((position, class_id))
+                } else {
+                    None
+                };
+                frame.push(PlayerFrame {
+                    slot: slot & !JOINED,
+                    buttons,
+                    axis,
+                    joined,
+                });
+            }
+            frames.push(frame);
+        }
+
+        let position_count = reader.u8()?;
+        let mut final_positions = Vec::with_capacity(position_count as usize);
+        for _ in 0..position_count {
+            final_positions.push((reader.u8()?, reader.vec3()?));
+        }
+
+        Ok(InputRecording {
+            frames,
+            final_positions,
+        })
+    }
+
+    /// Describes every recorded player that didn't end up where they did when
+    /// recording, or is missing altogether.
+    fn mismatched_positions(&self, positions: &HashMap<u8, Vec3>) -> Vec<String> {
+        self.final_positions
+            .iter()
+            .filter_map(|(slot, expected)| match positions.get(slot) {
+                Some(actual) if actual.distance(*expected) <= POSITION_TOLERANCE => None,
+                Some(actual) => Some(format!(
+                    "Player {} ended at {:?}, expected {:?}",
+                    slot + 1,
+                    actual,
+                    expected
+                )),
+                None => Some(format!(
+                    "Player {} is missing, expected at {:?}",
+                    slot + 1,
+                    expected
+                )),
+            })
+            .collect()
+    }
+}
+
+fn write_vec3(bytes: &mut Vec<u8>, value: Vec3) {
+    for component in value.to_array() {
+        bytes.extend_from_slice(&component.to_le_bytes());
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let slice = self
+            .bytes
+            .get(self.at..self.at + count)
+            .ok_or_else(|| "replay file ends early".to_string())?;
+        self.at += count;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, String> {
+        Ok(Vec3::new(
+            f32::from_le_bytes(self.array()?),
+            f32::from_le_bytes(self.array()?),
+            f32::from_le_bytes(self.array()?),
+        ))
+    }
+}
+
+#[derive(Resource, Default)]
+struct ReplayCursor {
+    tick: usize,
+    finished: bool,
+}
+
+fn exit_app(mut exit_writer: EventWriter<AppExit>) {
+    exit_writer.send(AppExit);
+}
+
+fn fix_physics_timestep(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: ReplayMode::tick().as_secs_f32(),
+        substeps: 1,
+    };
+}
+
+fn record_player_actions(
+    mut recording: ResMut<InputRecording>,
+    mut seen: Local<Vec<u8>>,
+    player_query: Query<
+        (
+            &PlayerSlot,
+            &PlayerClass,
+            &ActionState<PlayerAction>,
+            &Transform,
+        ),
+        With<Player>,
+    >,
+) {
+    let mut players: Vec<_> = player_query.iter().collect();
+    players.sort_by_key(|(slot, ..)| slot.0);
+
+    let frame = players
+        .into_iter()
+        .map(|(slot, class, action_state, transform)| {
+            let slot = slot.0 as u8;
+            let mut frame = PlayerFrame::capture(slot, action_state);
+            if !seen.contains(&slot) {
+                seen.push(slot);
+                frame.joined = Some((transform.translation, class.0));
+            }
+            frame
+        })
+        .collect();
+    recording.frames.push(frame);
+}
+
+fn save_recording(
+    mode: Res<ReplayMode>,
+    mut recording: ResMut<InputRecording>,
+    mut exit_reader: EventReader<AppExit>,
+    player_query: Query<(&PlayerSlot, &Transform), With<Player>>,
+) {
+    if exit_reader.iter().next().is_none() {
+        return;
+    }
+    let ReplayMode::Record(path) = mode.as_ref() else {
+        return;
+    };
+
+    let mut final_positions: Vec<_> = player_query
+        .iter()
+        .map(|(slot, transform)| (slot.0 as u8, transform.translation))
+        .collect();
+    final_positions.sort_by_key(|(slot, _)| *slot);
+    recording.final_positions = final_positions;
+
+    match recording.write(path) {
+        Ok(()) => info!(
+            "Recorded {} ticks to {}",
+            recording.frames.len(),
+            path.display()
+        ),
+        Err(error) => warn!("Could not write replay {}: {}", path.display(), error),
+    }
+}
+
+/// Replayed players are driven only by the recording, never by live devices.
+fn detach_live_input(
+    mut commands: Commands,
+    player_query: Query<Entity, (With<Player>, With<InputMap<PlayerAction>>)>,
+) {
+    for entity in &player_query {
+        commands.entity(entity).remove::<InputMap<PlayerAction>>();
+    }
+}
+
+/// Replays start as the class the first player picked when recording.
+fn skip_main_menu(
+    recording: Res<InputRecording>,
+    mut selected: ResMut<SelectedClass>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let Some((_, class_id)) = recording
+        .frames
+        .iter()
+        .flatten()
+        .find(|player| player.slot == 0)
+        .and_then(|player| player.joined)
+    {
+        selected.0 = class_id;
+    }
+    next_state.set(GameState::Gameplay);
+}
+
+fn replay_player_actions(
+    mut commands: Commands,
+    characters: CharacterAssets,
+    bindings: Res<InputBindings>,
+    recording: Res<InputRecording>,
+    mut cursor: ResMut<ReplayCursor>,
+    mut player_query: Query<(&PlayerSlot, &mut ActionState<PlayerAction>), With<Player>>,
+) {
+    let Some(frame) = recording.frames.get(cursor.tick) else {
+        if !cursor.finished {
+            cursor.finished = true;
+            for (_, mut action_state) in &mut player_query {
+                *action_state = ActionState::default();
+            }
+        }
+        return;
+    };
+    cursor.tick += 1;
+
+    let mut players: HashMap<u8, Mut<ActionState<PlayerAction>>> = player_query
+        .iter_mut()
+        .map(|(slot, action_state)| (slot.0 as u8, action_state))
+        .collect();
+
+    for player in frame {
+        match players.get_mut(&player.slot) {
+            Some(action_state) => player.apply(action_state),
+            None => {
+                if let Some((translation, class_id)) = player.joined {
+                    spawn_player_character(
+                        &mut commands,
+                        &characters,
+                        &bindings,
+                        class_id,
+                        PlayerSlot(player.slot as usize),
+                        InputDevices {
+                            keyboard: false,
+                            gamepad: None,
+                        },
+                        translation,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_replay_positions(
+    recording: Res<InputRecording>,
+    cursor: Res<ReplayCursor>,
+    mut exit_writer: EventWriter<AppExit>,
+    player_query: Query<(&PlayerSlot, &Transform), With<Player>>,
+) {
+    if !cursor.finished {
+        return;
+    }
+
+    let positions: HashMap<u8, Vec3> = player_query
+        .iter()
+        .map(|(slot, transform)| (slot.0 as u8, transform.translation))
+        .collect();
+
+    let mismatched = recording.mismatched_positions(&positions);
+    for mismatch in &mismatched {
+        error!("{}", mismatch);
+    }
+
+    if !mismatched.is_empty() {
+        error!("Replay check failed for {} player(s)", mismatched.len());
+        // `AppExit` can't carry a status code, and scripts need to see the failure.
+        std::process::exit(1);
+    }
+    info!("Replay check passed after {} ticks", recording.frames.len());
+    exit_writer.send(AppExit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/two_players.replay");
+
+    fn recording() -> InputRecording {
+        InputRecording {
+            frames: vec![
+                vec![PlayerFrame {
+                    slot: 0,
+                    buttons: 0,
+                    axis: (0, i16::MAX),
+                    joined: Some((Vec3::new(5.0, 1.0, 5.0), ClassId::Knight)),
+                }],
+                vec![
+                    PlayerFrame {
+                        slot: 0,
+                        buttons: 0b10,
+                        axis: (-120, i16::MAX),
+                        joined: None,
+                    },
+                    PlayerFrame {
+                        slot: 1,
+                        buttons: 0,
+                        axis: (0, 0),
+                        joined: Some((Vec3::new(6.5, 1.0, 5.0), ClassId::Barbarian)),
+                    },
+                ],
+            ],
+            final_positions: vec![(0, Vec3::new(5.0, 1.0, 7.5)), (1, Vec3::new(6.5, 1.0, 5.0))],
+        }
+    }
+
+    #[test]
+    fn decodes_the_fixture() {
+        let recording = InputRecording::decode(FIXTURE).unwrap();
+
+        assert_eq!(recording.frames.len(), 3);
+        assert_eq!(
+            recording.frames[0][0].joined,
+            Some((Vec3::new(5.0, 1.0, 5.0), ClassId::Knight))
+        );
+        assert_eq!(recording.frames[1][0].buttons, 0b10);
+        assert_eq!(recording.frames[2][1].slot, 1);
+        assert_eq!(
+            recording.frames[2][1].joined,
+            Some((Vec3::new(6.5, 1.0, 5.0), ClassId::Mage))
+        );
+        assert_eq!(
+            recording.final_positions,
+            vec![(0, Vec3::new(5.0, 1.0, 7.5)), (1, Vec3::new(6.5, 1.0, 5.0))]
+        );
+        assert_eq!(recording.encode(), FIXTURE);
+    }
+
+    #[test]
+    fn round_trips_a_recording() {
+        let recording = recording();
+        let decoded = InputRecording::decode(&recording.encode()).unwrap();
+        assert_eq!(decoded, recording);
+    }
+
+    #[test]
+    fn stores_the_class_after_the_join_position() {
+        let bytes = recording().encode();
+        // Header, the frame count, one player and its joined position.
+        let class_at = 4 + 1 + 2 + 4 + 1 + 6 + 12;
+        assert_eq!(bytes[4], 2);
+        assert_eq!(bytes[class_at], ClassId::Knight.index());
+    }
+
+    #[test]
+    fn rejects_an_unknown_class() {
+        let mut bytes = recording().encode();
+        let class_at = 4 + 1 + 2 + 4 + 1 + 6 + 12;
+        bytes[class_at] = 200;
+
+        let error = InputRecording::decode(&bytes).unwrap_err();
+        assert_eq!(error, "unknown class 200");
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = recording().encode();
+        bytes[4] = 1;
+        assert!(InputRecording::decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn reports_players_that_drifted_or_went_missing() {
+        let recording = recording();
+        let positions = HashMap::from_iter([(0, Vec3::new(5.0, 1.0, 7.52))]);
+        assert_eq!(recording.mismatched_positions(&positions).len(), 1);
+
+        let positions = HashMap::from_iter([(0, Vec3::new(5.0, 1.0, 9.0)), (1, Vec3::ZERO)]);
+        assert_eq!(recording.mismatched_positions(&positions).len(), 2);
+    }
+}
@@ -0,0 +1,89 @@
+use super::{interaction_sensor, Hinge, InteractKind, Interactable};
+use crate::inventory::{spawn_pickup, Inventory, ItemCatalog, ItemStack};
+use crate::player::{PlayerIntent, PlayerIntentEvent};
+use crate::{ItemCache, PropCache};
+use bevy::prelude::*;
+
+const CHEST_REACH: f32 = 1.6;
+const LID_SWING: f32 = -110.0;
+/// Loot that doesn't fit in the opener's inventory spills out this far in front of the chest.
+const SPILL_DISTANCE: f32 = 1.5;
+
+/// Gives its loot to whoever opens it, once.
+#[derive(Component, Debug)]
+pub struct Chest {
+    pub loot: Vec<ItemStack>,
+    pub opened: bool,
+}
+
+pub fn spawn_chest(
+    commands: &mut Commands,
+    props: &PropCache,
+    loot: Vec<ItemStack>,
+    transform: Transform,
+) -> Entity {
+    commands
+        .spawn((
+            Name::from("Chest"),
+            Chest {
+                loot,
+                opened: false,
+            },
+            Interactable::new("Open chest", InteractKind::Use),
+            Hinge::new("_lid", Vec3::X, LID_SWING, 1.2),
+            SceneBundle {
+                scene: props.chest.clone_weak(),
+                transform,
+                ..default()
+            },
+            interaction_sensor(CHEST_REACH),
+        ))
+        .id()
+}
+
+pub fn open_chests(
+    mut commands: Commands,
+    item_cache: Res<ItemCache>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    mut intent_reader: EventReader<PlayerIntentEvent>,
+    mut chest_query: Query<(&mut Chest, &mut Hinge, &Transform)>,
+    mut inventory_query: Query<&mut Inventory>,
+) {
+    let Some(catalog) = catalogs.get(&item_cache.catalog) else {
+        return;
+    };
+
+    for event in intent_reader.iter() {
+        let PlayerIntent::Interact { target, .. } = event.intent else {
+            continue;
+        };
+        let Ok((mut chest, mut hinge, transform)) = chest_query.get_mut(target) else {
+            continue;
+        };
+        if chest.opened {
+            continue;
+        }
+        chest.opened = true;
+        hinge.open = true;
+        commands.entity(target).remove::<Interactable>();
+
+        let mut inventory = inventory_query.get_mut(event.player).ok();
+        let spill = transform.translation + transform.forward() * SPILL_DISTANCE + Vec3::Y * 0.5;
+        for stack in chest.loot.drain(..) {
+            let Some(definition) = catalog.get(&stack.item) else {
+                warn!("Chest holds unknown item {}", stack.item);
+                continue;
+            };
+            let left = match inventory.as_mut() {
+                Some(inventory) => inventory.add(&stack.item, stack.count, definition.stack),
+                None => stack.count,
+            };
+            if left < stack.count {
+                info!("Found {} x{}", definition.name, stack.count - left);
+            }
+            if left > 0 {
+                spawn_pickup(&mut commands, catalog, &stack.item, left, spill);
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use super::{interaction_sensor, Hinge, InteractKind, Interactable, SignalEvent};
+use crate::inventory::Inventory;
+use crate::player::{PlayerIntent, PlayerIntentEvent};
+use crate::StructureCache;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const DOOR_REACH: f32 = 2.2;
+const DOOR_SWING: f32 = 95.0;
+
+/// A doorway's door. While closed its `blocker` collider fills the opening.
+/// Doors on a `channel` are worked by levers instead of by hand, and a door
+/// with a `key` stays shut until it is used by someone carrying that item.
+#[derive(Component, Debug)]
+pub struct Door {
+    pub open: bool,
+    pub channel: Option<u32>,
+    pub key: Option<String>,
+    blocker: Entity,
+}
+
+impl Door {
+    fn prompt(open: bool, locked: bool) -> &'static str {
+        if locked {
+            "Unlock"
+        } else if open {
+            "Close"
+        } else {
+            "Open"
+        }
+    }
+
+    fn set_open(&mut self, open: bool, hinge: &mut Hinge, commands: &mut Commands) {
+        self.open = open;
+        hinge.open = open;
+        if open {
+            commands.entity(self.blocker).insert(ColliderDisabled);
+        } else {
+            commands.entity(self.blocker).remove::<ColliderDisabled>();
+        }
+    }
+
+    /// Puts the door back the way a save left it.
+    pub(crate) fn restore(
+        &mut self,
+        entity: Entity,
+        open: bool,
+        locked: bool,
+        hinge: &mut Hinge,
+        interactable: Option<&mut Interactable>,
+        commands: &mut Commands,
+    ) {
+        if !locked && self.key.take().is_some() && self.channel.is_some() {
+            commands.entity(entity).remove::<Interactable>();
+        }
+        if self.open != open {
+            self.set_open(open, hinge, commands);
+        }
+        if let Some(interactable) = interactable {
+            interactable.prompt = Door::prompt(open, self.key.is_some()).to_string();
+        }
+    }
+}
+
+#[derive(Bundle)]
+pub struct DoorBundle {
+    pub door: Door,
+    pub hinge: Hinge,
+    pub sensor: (Collider, Sensor, ActiveEvents),
+}
+
+impl DoorBundle {
+    pub fn new(blocker: Entity, channel: Option<u32>, key: Option<String>) -> Self {
+        DoorBundle {
+            door: Door {
+                open: false,
+                channel,
+                key,
+                blocker,
+            },
+            hinge: Hinge::new("_door", Vec3::Y, DOOR_SWING, 1.5),
+            sensor: interaction_sensor(DOOR_REACH),
+        }
+    }
+
+    /// The collider that stops characters walking through the closed door.
+    pub fn blocker() -> impl Bundle {
+        (
+            Name::from("Door Blocker"),
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 1.5, 0.0)),
+            Collider::cuboid(1.0, 1.5, 0.2),
+            RigidBody::Fixed,
+        )
+    }
+
+    /// What a player is offered at a door they can work by hand or unlock.
+    pub fn interactable(locked: bool) -> Interactable {
+        Interactable::new(Door::prompt(false, locked), InteractKind::Use)
+    }
+}
+
+pub fn use_doors(
+    mut commands: Commands,
+    mut intent_reader: EventReader<PlayerIntentEvent>,
+    mut door_query: Query<(&mut Door, &mut Hinge, &mut Interactable)>,
+    mut inventory_query: Query<&mut Inventory>,
+) {
+    for event in intent_reader.iter() {
+        let PlayerIntent::Interact { target, .. } = event.intent else {
+            continue;
+        };
+        let Ok((mut door, mut hinge, mut interactable)) = door_query.get_mut(target) else {
+            continue;
+        };
+
+        if let Some(key) = door.key.clone() {
+            let unlocked = inventory_query
+                .get_mut(event.player)
+                .map_or(false, |mut inventory| inventory.remove(&key, 1));
+            if !unlocked {
+                info!("The door is locked, it needs a {}", key);
+                interactable.prompt = format!("Locked, needs a {}", key);
+                continue;
+            }
+            info!("Unlocked the door with a {}", key);
+            door.key = None;
+            // Lever doors only needed unlocking, from now on the lever works them.
+            if door.channel.is_some() {
+                commands.entity(target).remove::<Interactable>();
+                continue;
+            }
+        }
+
+        let open = !door.open;
+        door.set_open(open, &mut hinge, &mut commands);
+        interactable.prompt = Door::prompt(open, false).to_string();
+    }
+}
+
+/// Locked doors are built with a gate across them, which comes down once unlocked.
+pub fn swap_unlocked_doors(
+    structures: Res<StructureCache>,
+    mut door_query: Query<(&Door, &mut Hinge, &mut Handle<Scene>), Changed<Door>>,
+) {
+    for (door, mut hinge, mut scene) in &mut door_query {
+        if door.key.is_none() && *scene == structures.gated_door {
+            *scene = structures.door.clone_weak();
+            hinge.reset_part();
+        }
+    }
+}
+
+pub fn receive_signals(
+    mut commands: Commands,
+    mut signal_reader: EventReader<SignalEvent>,
+    mut door_query: Query<(&mut Door, &mut Hinge)>,
+) {
+    for signal in signal_reader.iter() {
+        for (mut door, mut hinge) in &mut door_query {
+            if door.channel == Some(signal.channel)
+                && door.key.is_none()
+                && door.open != signal.active
+            {
+                door.set_open(signal.active, &mut hinge, &mut commands);
+            }
+        }
+    }
+}
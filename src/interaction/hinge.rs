@@ -0,0 +1,78 @@
+use super::find_part;
+use bevy::prelude::*;
+
+/// Swings a named part of an object's scene, such as a door leaf or a chest lid,
+/// between closed and `angle` degrees around `axis`.
+#[derive(Component, Debug)]
+pub struct Hinge {
+    part: &'static str,
+    axis: Vec3,
+    angle: f32,
+    /// Turns per second, as a fraction of the whole swing.
+    speed: f32,
+    pub open: bool,
+    amount: f32,
+    node: Option<(Entity, Quat)>,
+}
+
+impl Hinge {
+    pub fn new(part: &'static str, axis: Vec3, angle: f32, speed: f32) -> Self {
+        Hinge {
+            part,
+            axis,
+            angle,
+            speed,
+            open: false,
+            amount: 0.0,
+            node: None,
+        }
+    }
+
+    /// Starts over from closed on a freshly spawned scene, whose part the hinge
+    /// has not found yet.
+    pub fn reset_part(&mut self) {
+        self.node = None;
+        self.amount = 0.0;
+    }
+}
+
+pub fn swing_hinges(
+    time: Res<Time>,
+    mut hinge_query: Query<(Entity, &mut Hinge)>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    for (entity, mut hinge) in &mut hinge_query {
+        // The part only exists once the object's scene has spawned.
+        if hinge.node.is_none() {
+            let Some(part) = find_part(entity, hinge.part, &children_query, &name_query) else {
+                continue;
+            };
+            let Ok(transform) = transform_query.get(part) else {
+                continue;
+            };
+            hinge.node = Some((part, transform.rotation));
+        }
+
+        let target = if hinge.open { 1.0 } else { 0.0 };
+        if hinge.amount == target {
+            continue;
+        }
+        let step = hinge.speed * time.delta_seconds();
+        hinge.amount = if hinge.amount < target {
+            (hinge.amount + step).min(target)
+        } else {
+            (hinge.amount - step).max(target)
+        };
+
+        let Some((part, closed)) = hinge.node else {
+            continue;
+        };
+        if let Ok(mut transform) = transform_query.get_mut(part) {
+            let swing =
+                Quat::from_axis_angle(hinge.axis, (hinge.angle * hinge.amount).to_radians());
+            transform.rotation = closed * swing;
+        }
+    }
+}
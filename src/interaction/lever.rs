@@ -0,0 +1,64 @@
+use super::{interaction_sensor, Hinge, InteractKind, Interactable, SignalEvent};
+use crate::player::{PlayerIntent, PlayerIntentEvent};
+use crate::PropCache;
+use bevy::prelude::*;
+
+const LEVER_REACH: f32 = 1.4;
+const LEVER_THROW: f32 = -60.0;
+
+/// Switches everything listening on `channel` each time it is pulled.
+#[derive(Component, Debug)]
+pub struct Lever {
+    pub on: bool,
+    pub channel: u32,
+}
+
+pub fn spawn_lever(
+    commands: &mut Commands,
+    props: &PropCache,
+    channel: u32,
+    transform: Transform,
+) -> Entity {
+    commands
+        .spawn((
+            Name::from(format!("Lever {}", channel)),
+            Lever { on: false, channel },
+            Interactable::new("Pull lever", InteractKind::Use),
+            Hinge::new("lever_handle", Vec3::X, LEVER_THROW, 3.0),
+            SpatialBundle::from_transform(transform),
+            interaction_sensor(LEVER_REACH),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Name::from("lever_handle"),
+                SceneBundle {
+                    scene: props.lever.clone_weak(),
+                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                    ..default()
+                },
+            ));
+        })
+        .id()
+}
+
+pub fn pull_levers(
+    mut intent_reader: EventReader<PlayerIntentEvent>,
+    mut signal_writer: EventWriter<SignalEvent>,
+    mut lever_query: Query<(&mut Lever, &mut Hinge)>,
+) {
+    for event in intent_reader.iter() {
+        let PlayerIntent::Interact { target, .. } = event.intent else {
+            continue;
+        };
+        let Ok((mut lever, mut hinge)) = lever_query.get_mut(target) else {
+            continue;
+        };
+
+        lever.on = !lever.on;
+        hinge.open = lever.on;
+        signal_writer.send(SignalEvent {
+            channel: lever.channel,
+            active: lever.on,
+        });
+    }
+}
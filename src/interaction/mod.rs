@@ -0,0 +1,282 @@
+use crate::animation::{AnimationState, AnimationSystems};
+use crate::camera::MainCamera;
+use crate::input::{InputBindings, InputDevices, PlayerAction};
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+mod chest;
+mod door;
+mod hinge;
+mod lever;
+
+pub use chest::{spawn_chest, Chest};
+pub use door::{Door, DoorBundle};
+pub use hinge::Hinge;
+pub use lever::{spawn_lever, Lever};
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SignalEvent>().add_systems(
+            Update,
+            (
+                track_interaction_reach,
+                update_interaction_focus,
+                lever::pull_levers,
+                door::use_doors,
+                door::swap_unlocked_doors,
+                door::receive_signals,
+                chest::open_chests,
+                hinge::swing_hinges,
+                update_interaction_prompts,
+            )
+                .chain()
+                .after(AnimationSystems::UpdateState)
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+const PROMPT_HEIGHT: f32 = 2.4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InteractKind {
+    Use,
+    PickUp,
+}
+
+impl InteractKind {
+    /// The animation the character plays, and how long it holds them.
+    pub fn animation(self) -> (AnimationState, f32) {
+        match self {
+            InteractKind::Use => (AnimationState::Interacting, 1.2),
+            InteractKind::PickUp => (AnimationState::PickingUp, 1.0),
+        }
+    }
+}
+
+/// Something a character can interact with while standing in its sensor.
+/// Remove the component to stop offering the interaction.
+#[derive(Component, Clone, Debug)]
+pub struct Interactable {
+    pub prompt: String,
+    pub kind: InteractKind,
+}
+
+impl Interactable {
+    pub fn new(prompt: impl Into<String>, kind: InteractKind) -> Self {
+        Interactable {
+            prompt: prompt.into(),
+            kind,
+        }
+    }
+}
+
+/// The interactables whose sensors a character is standing in.
+#[derive(Component, Default, Debug)]
+pub struct InteractionReach {
+    in_reach: Vec<Entity>,
+}
+
+/// The closest interactable in reach, which the interact action is used on.
+#[derive(Component, Default, Debug)]
+pub struct InteractionFocus {
+    target: Option<(Entity, InteractKind)>,
+}
+
+impl InteractionFocus {
+    pub fn target(&self) -> Option<(Entity, InteractKind)> {
+        self.target
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct InteractorBundle {
+    pub reach: InteractionReach,
+    pub focus: InteractionFocus,
+}
+
+/// Sent by levers and anything else that switches other room objects. Objects
+/// listening on the same `channel` follow `active`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SignalEvent {
+    pub channel: u32,
+    pub active: bool,
+}
+
+/// The floating prompt above the object a player is focused on.
+#[derive(Component)]
+struct InteractionPrompt {
+    player: Entity,
+}
+
+fn track_interaction_reach(
+    mut collision_reader: EventReader<CollisionEvent>,
+    interactable_query: Query<(), With<Interactable>>,
+    mut reach_query: Query<&mut InteractionReach>,
+) {
+    for event in collision_reader.iter() {
+        let (first, second, entered) = match event {
+            CollisionEvent::Started(first, second, _) => (*first, *second, true),
+            CollisionEvent::Stopped(first, second, _) => (*first, *second, false),
+        };
+
+        for (target, character) in [(first, second), (second, first)] {
+            if !interactable_query.contains(target) {
+                continue;
+            }
+            let Ok(mut reach) = reach_query.get_mut(character) else {
+                continue;
+            };
+            reach.in_reach.retain(|entity| *entity != target);
+            if entered {
+                reach.in_reach.push(target);
+            }
+        }
+    }
+}
+
+fn update_interaction_focus(
+    mut character_query: Query<(
+        &GlobalTransform,
+        &mut InteractionReach,
+        &mut InteractionFocus,
+    )>,
+    interactable_query: Query<(&GlobalTransform, &Interactable)>,
+) {
+    for (transform, mut reach, mut focus) in &mut character_query {
+        reach
+            .in_reach
+            .retain(|entity| interactable_query.contains(*entity));
+
+        let position = transform.translation();
+        let target = reach
+            .in_reach
+            .iter()
+            .filter_map(|entity| {
+                let (target_transform, interactable) = interactable_query.get(*entity).ok()?;
+                let distance = target_transform.translation().distance_squared(position);
+                Some((*entity, interactable.kind, distance))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+            .map(|(entity, kind, _)| (entity, kind));
+
+        if focus.target != target {
+            focus.target = target;
+        }
+    }
+}
+
+fn update_interaction_prompts(
+    mut commands: Commands,
+    bindings: Res<InputBindings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    player_query: Query<(Entity, &InteractionFocus, Option<&InputDevices>)>,
+    interactable_query: Query<(&GlobalTransform, &Interactable)>,
+    mut prompt_query: Query<(Entity, &InteractionPrompt, &mut Style, &mut Text)>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let mut shown = Vec::new();
+    for (entity, prompt, mut style, mut text) in &mut prompt_query {
+        let target = player_query
+            .get(prompt.player)
+            .ok()
+            .and_then(|(_, focus, devices)| {
+                let (target, _) = focus.target()?;
+                let (transform, interactable) = interactable_query.get(target).ok()?;
+                Some((transform, interactable, devices))
+            });
+        let Some((transform, interactable, devices)) = target else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        shown.push(prompt.player);
+
+        let world = transform.translation() + Vec3::Y * PROMPT_HEIGHT;
+        match camera.world_to_viewport(camera_transform, world) {
+            Some(position) => {
+                style.display = Display::Flex;
+                style.left = Val::Px(position.x);
+                style.top = Val::Px(position.y);
+            }
+            None => style.display = Display::None,
+        }
+        text.sections[0].value = prompt_text(&bindings, devices, interactable);
+    }
+
+    for (player, focus, devices) in &player_query {
+        if shown.contains(&player) {
+            continue;
+        }
+        let Some((_, interactable)) = focus
+            .target()
+            .and_then(|(target, _)| interactable_query.get(target).ok())
+        else {
+            continue;
+        };
+        commands.spawn((
+            Name::from("Interaction Prompt"),
+            InteractionPrompt { player },
+            TextBundle::from_section(
+                prompt_text(&bindings, devices, interactable),
+                TextStyle {
+                    font_size: 22.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                display: Display::None,
+                ..default()
+            }),
+        ));
+    }
+}
+
+fn prompt_text(
+    bindings: &InputBindings,
+    devices: Option<&InputDevices>,
+    interactable: &Interactable,
+) -> String {
+    let gamepad = devices.map_or(false, |devices| devices.gamepad.is_some());
+    match bindings.label(PlayerAction::Interact, gamepad) {
+        Some(label) => format!("[{}] {}", label, interactable.prompt),
+        None => interactable.prompt.clone(),
+    }
+}
+
+/// Finds a node of a spawned scene by the end of its name.
+pub(crate) fn find_part(
+    entity: Entity,
+    suffix: &str,
+    children_query: &Query<&Children>,
+    name_query: &Query<&Name>,
+) -> Option<Entity> {
+    for child in children_query.get(entity).ok()?.iter() {
+        if name_query
+            .get(*child)
+            .map_or(false, |name| name.as_str().ends_with(suffix))
+        {
+            return Some(*child);
+        }
+        if let Some(found) = find_part(*child, suffix, children_query, name_query) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// The sensor every interactable carries, sized to how close characters need to be.
+fn interaction_sensor(radius: f32) -> (Collider, Sensor, ActiveEvents) {
+    (
+        Collider::ball(radius),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+    )
+}
@@ -0,0 +1,123 @@
+use super::{Inventory, ItemCatalog, ItemEffect};
+use crate::combat::Health;
+use crate::player::{PlayerIntent, PlayerIntentEvent};
+use crate::ItemCache;
+use bevy::prelude::*;
+
+/// Using an item takes one of the first items in the inventory that would do
+/// something, and applies its effect.
+pub fn use_items(
+    item_cache: Res<ItemCache>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    mut intent_reader: EventReader<PlayerIntentEvent>,
+    mut character_query: Query<(&mut Inventory, &mut Health, Option<&Name>)>,
+) {
+    let Some(catalog) = catalogs.get(&item_cache.catalog) else {
+        return;
+    };
+
+    for event in intent_reader.iter() {
+        if event.intent != PlayerIntent::UseItem {
+            continue;
+        }
+        let Ok((mut inventory, mut health, name)) = character_query.get_mut(event.player) else {
+            continue;
+        };
+        let name = name.map_or("Someone", |name| name.as_str());
+
+        let usable = inventory.stacks().iter().find_map(|stack| {
+            let definition = catalog.get(&stack.item)?;
+            let effect = definition.effect?;
+            let useful = match effect {
+                ItemEffect::Heal(_) => health.current() < health.max(),
+            };
+            useful.then(|| (stack.item.clone(), definition, effect))
+        });
+        let Some((item, definition, effect)) = usable else {
+            info!("{} has nothing to use", name);
+            continue;
+        };
+
+        inventory.remove(&item, 1);
+        match effect {
+            ItemEffect::Heal(amount) => health.heal(amount),
+        }
+        info!("{} used a {}", name, definition.name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ItemDefinition;
+    use bevy::utils::HashMap;
+
+    fn app() -> App {
+        let mut catalog = ItemCatalog {
+            items: HashMap::default(),
+        };
+        for (id, effect) in [("coin", None), ("potion", Some(ItemEffect::Heal(40.0)))] {
+            catalog.items.insert(
+                id.to_string(),
+                ItemDefinition {
+                    name: id.to_string(),
+                    model: Handle::default(),
+                    stack: 5,
+                    effect,
+                    equip: None,
+                },
+            );
+        }
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<ItemCatalog>()
+            .add_event::<PlayerIntentEvent>()
+            .add_systems(Update, use_items);
+        let catalog = app.world.resource_mut::<Assets<ItemCatalog>>().add(catalog);
+        app.insert_resource(ItemCache { catalog });
+        app
+    }
+
+    fn use_item(app: &mut App, player: Entity) {
+        app.world
+            .resource_mut::<Events<PlayerIntentEvent>>()
+            .send(PlayerIntentEvent {
+                player,
+                intent: PlayerIntent::UseItem,
+            });
+        app.update();
+    }
+
+    #[test]
+    fn drinking_a_potion_heals() {
+        let mut app = app();
+        let mut inventory = Inventory::default();
+        inventory.add("coin", 3, 5);
+        inventory.add("potion", 2, 5);
+        let mut health = Health::new(100.0);
+        health.damage(60.0);
+        let player = app.world.spawn((inventory, health)).id();
+
+        use_item(&mut app, player);
+
+        let player = app.world.entity(player);
+        assert_eq!(player.get::<Health>().unwrap().current(), 80.0);
+        let inventory = player.get::<Inventory>().unwrap();
+        assert_eq!(inventory.count("potion"), 1);
+        assert_eq!(inventory.count("coin"), 3);
+    }
+
+    #[test]
+    fn potions_are_kept_at_full_health() {
+        let mut app = app();
+        let mut inventory = Inventory::default();
+        inventory.add("potion", 1, 5);
+        let player = app.world.spawn((inventory, Health::new(100.0))).id();
+
+        use_item(&mut app, player);
+
+        let inventory = app.world.get::<Inventory>(player).unwrap();
+        assert_eq!(inventory.count("potion"), 1);
+    }
+}
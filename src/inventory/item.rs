@@ -0,0 +1,116 @@
+use crate::equipment::{EquipmentItem, EquipmentSlot};
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ItemFile {
+    name: String,
+    model: String,
+    #[serde(default = "default_stack")]
+    stack: u32,
+    #[serde(default)]
+    effect: Option<ItemEffect>,
+    #[serde(default)]
+    equip: Option<(EquipmentSlot, EquipmentItem)>,
+}
+
+fn default_stack() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+struct ItemCatalogFile {
+    items: HashMap<String, ItemFile>,
+}
+
+/// What using an item does. Items without an effect can't be used.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ItemEffect {
+    /// Restores this much health.
+    Heal(f32),
+}
+
+#[derive(Debug)]
+pub struct ItemDefinition {
+    pub name: String,
+    pub model: Handle<Scene>,
+    /// How many fit in one inventory slot.
+    pub stack: u32,
+    pub effect: Option<ItemEffect>,
+    /// Gear is equipped straight from the ground instead of being carried.
+    pub equip: Option<(EquipmentSlot, EquipmentItem)>,
+}
+
+/// Every item in the game keyed by id, loaded from an `items.ron` file.
+/// Rooms and loot tables refer to items by these ids.
+#[derive(TypeUuid, TypePath, Debug)]
+#[uuid = "5e2b8d14-7c3a-4f91-b6e0-9a4d2c7f1b83"]
+pub struct ItemCatalog {
+    pub items: HashMap<String, ItemDefinition>,
+}
+
+impl ItemCatalog {
+    pub fn get(&self, item: &str) -> Option<&ItemDefinition> {
+        self.items.get(item)
+    }
+
+    /// The id of the item that equips `item` in `slot`.
+    pub fn equippable(&self, slot: EquipmentSlot, item: &EquipmentItem) -> Option<&str> {
+        self.items
+            .iter()
+            .find(|(_, definition)| {
+                definition
+                    .equip
+                    .as_ref()
+                    .map_or(false, |(equip_slot, equip_item)| {
+                        *equip_slot == slot && equip_item == item
+                    })
+            })
+            .map(|(id, _)| id.as_str())
+    }
+}
+
+#[derive(Default)]
+pub struct ItemCatalogLoader;
+
+impl AssetLoader for ItemCatalogLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file: ItemCatalogFile = ron::de::from_bytes(bytes)?;
+            let mut dependencies: Vec<AssetPath<'static>> = Vec::new();
+
+            let items = file
+                .items
+                .into_iter()
+                .map(|(id, item)| {
+                    let model_path = AssetPath::from(item.model.as_str()).to_owned();
+                    dependencies.push(model_path.clone());
+                    let definition = ItemDefinition {
+                        name: item.name,
+                        model: load_context.get_handle(model_path),
+                        stack: item.stack,
+                        effect: item.effect,
+                        equip: item.equip,
+                    };
+                    (id, definition)
+                })
+                .collect();
+
+            load_context.set_default_asset(
+                LoadedAsset::new(ItemCatalog { items }).with_dependencies(dependencies),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["items.ron"]
+    }
+}
@@ -0,0 +1,199 @@
+use crate::animation::AnimationSystems;
+use crate::GameState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod consumable;
+mod item;
+mod pickup;
+
+pub use item::{ItemCatalog, ItemDefinition, ItemEffect};
+pub use pickup::{spawn_pickup, Pickup};
+
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<ItemCatalog>()
+            .init_asset_loader::<item::ItemCatalogLoader>()
+            .register_type::<Inventory>()
+            .register_type::<ItemStack>()
+            .register_type::<Vec<ItemStack>>()
+            .add_systems(
+                Update,
+                (pickup::collect_pickups, consumable::use_items)
+                    .after(AnimationSystems::UpdateState)
+                    .run_if(in_state(GameState::Gameplay)),
+            );
+    }
+}
+
+const INVENTORY_CAPACITY: usize = 16;
+
+#[derive(Reflect, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+/// Items a character carries. Each slot holds one stack of up to the item's
+/// `stack` size, and new items top up existing stacks before taking a slot.
+#[derive(Component, Reflect, Clone, Debug, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Inventory {
+    stacks: Vec<ItemStack>,
+    capacity: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory {
+            stacks: Vec::new(),
+            capacity: INVENTORY_CAPACITY,
+        }
+    }
+}
+
+impl Inventory {
+    pub fn stacks(&self) -> &[ItemStack] {
+        &self.stacks
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.stacks
+            .iter()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        self.count(item) > 0
+    }
+
+    /// Adds `count` of `item` and returns how many didn't fit.
+    pub fn add(&mut self, item: &str, count: u32, max_stack: u32) -> u32 {
+        let max_stack = max_stack.max(1);
+        let mut remaining = count;
+
+        for stack in self.stacks.iter_mut().filter(|stack| stack.item == item) {
+            let added = remaining.min(max_stack.saturating_sub(stack.count));
+            stack.count += added;
+            remaining -= added;
+        }
+
+        while remaining > 0 && self.stacks.len() < self.capacity {
+            let added = remaining.min(max_stack);
+            self.stacks.push(ItemStack {
+                item: item.to_string(),
+                count: added,
+            });
+            remaining -= added;
+        }
+
+        remaining
+    }
+
+    /// Takes `count` of `item`, or nothing if there aren't that many.
+    pub fn remove(&mut self, item: &str, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+
+        let mut remaining = count;
+        for stack in self
+            .stacks
+            .iter_mut()
+            .rev()
+            .filter(|stack| stack.item == item)
+        {
+            let taken = remaining.min(stack.count);
+            stack.count -= taken;
+            remaining -= taken;
+        }
+        self.stacks.retain(|stack| stack.count > 0);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(inventory: &Inventory) -> Vec<(&str, u32)> {
+        inventory
+            .stacks()
+            .iter()
+            .map(|stack| (stack.item.as_str(), stack.count))
+            .collect()
+    }
+
+    #[test]
+    fn items_top_up_existing_stacks() {
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.add("coin", 3, 10), 0);
+        assert_eq!(inventory.add("key", 1, 1), 0);
+        assert_eq!(inventory.add("coin", 4, 10), 0);
+
+        assert_eq!(counts(&inventory), vec![("coin", 7), ("key", 1)]);
+        assert_eq!(inventory.count("coin"), 7);
+        assert!(inventory.contains("key"));
+        assert!(!inventory.contains("potion"));
+    }
+
+    #[test]
+    fn full_stacks_overflow_into_new_slots() {
+        let mut inventory = Inventory::default();
+        inventory.add("coin", 8, 10);
+        assert_eq!(inventory.add("coin", 15, 10), 0);
+
+        assert_eq!(
+            counts(&inventory),
+            vec![("coin", 10), ("coin", 10), ("coin", 3)]
+        );
+        assert_eq!(inventory.count("coin"), 23);
+    }
+
+    #[test]
+    fn a_full_inventory_takes_what_fits() {
+        let mut inventory = Inventory::default();
+        for _ in 0..INVENTORY_CAPACITY - 1 {
+            inventory.add("key", 1, 1);
+        }
+        inventory.add("coin", 8, 10);
+
+        // The coin stack tops up and nothing else has a slot.
+        assert_eq!(inventory.add("coin", 5, 10), 3);
+        assert_eq!(inventory.add("potion", 2, 5), 2);
+        assert_eq!(inventory.count("coin"), 10);
+        assert!(!inventory.contains("potion"));
+        assert_eq!(inventory.stacks().len(), INVENTORY_CAPACITY);
+    }
+
+    #[test]
+    fn removing_takes_from_the_last_stacks_first() {
+        let mut inventory = Inventory::default();
+        inventory.add("coin", 23, 10);
+        inventory.add("key", 1, 1);
+
+        assert!(inventory.remove("coin", 5));
+        assert_eq!(
+            counts(&inventory),
+            vec![("coin", 10), ("coin", 8), ("key", 1)]
+        );
+
+        assert!(inventory.remove("key", 1));
+        assert!(!inventory.contains("key"));
+        assert_eq!(inventory.stacks().len(), 2);
+    }
+
+    #[test]
+    fn removing_more_than_is_carried_takes_nothing() {
+        let mut inventory = Inventory::default();
+        inventory.add("coin", 4, 10);
+
+        assert!(!inventory.remove("coin", 5));
+        assert!(!inventory.remove("key", 1));
+        assert_eq!(inventory.count("coin"), 4);
+    }
+}
@@ -0,0 +1,121 @@
+use super::{Inventory, ItemCatalog};
+use crate::equipment::{EquipItemEvent, Loadout};
+use crate::interaction::{InteractKind, Interactable};
+use crate::player::{PlayerIntent, PlayerIntentEvent};
+use crate::ItemCache;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+const PICKUP_RADIUS: f32 = 1.2;
+
+/// An item lying in the world, collected by interacting with it.
+#[derive(Component, Clone, Debug)]
+pub struct Pickup {
+    pub item: String,
+    pub count: u32,
+}
+
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    catalog: &ItemCatalog,
+    item: &str,
+    count: u32,
+    translation: Vec3,
+) -> Option<Entity> {
+    let Some(definition) = catalog.get(item) else {
+        warn!("No item called {} in the catalog, skipping pickup", item);
+        return None;
+    };
+
+    let pickup = commands
+        .spawn((
+            Name::from(format!("Pickup: {}", definition.name)),
+            Pickup {
+                item: item.to_string(),
+                count,
+            },
+            Interactable::new(format!("Pick up {}", definition.name), InteractKind::PickUp),
+            SceneBundle {
+                scene: definition.model.clone_weak(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Collider::ball(PICKUP_RADIUS),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+        ))
+        .id();
+    Some(pickup)
+}
+
+/// Moves a picked up item into the character's inventory. Whatever doesn't
+/// fit stays on the ground. Gear is equipped instead, and whatever it replaces
+/// is left in its place.
+pub fn collect_pickups(
+    mut commands: Commands,
+    item_cache: Res<ItemCache>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    mut intent_reader: EventReader<PlayerIntentEvent>,
+    mut equip_writer: EventWriter<EquipItemEvent>,
+    mut pickup_query: Query<(&mut Pickup, &Transform)>,
+    mut character_query: Query<(&mut Inventory, Option<&Loadout>, Option<&Name>)>,
+) {
+    let Some(catalog) = catalogs.get(&item_cache.catalog) else {
+        return;
+    };
+
+    for event in intent_reader.iter() {
+        let PlayerIntent::Interact { target: entity, .. } = event.intent else {
+            continue;
+        };
+        let (Ok((mut pickup, transform)), Ok((mut inventory, loadout, name))) = (
+            pickup_query.get_mut(entity),
+            character_query.get_mut(event.player),
+        ) else {
+            continue;
+        };
+        let Some(definition) = catalog.get(&pickup.item) else {
+            continue;
+        };
+        if pickup.count == 0 {
+            continue;
+        }
+        let name = name.map_or("Someone", |name| name.as_str());
+
+        if let Some((slot, item)) = &definition.equip {
+            let Some(loadout) = loadout else {
+                continue;
+            };
+            let previous = loadout.slots.get(slot);
+            if previous == Some(item) {
+                continue;
+            }
+            if let Some(previous) = previous.and_then(|item| catalog.equippable(*slot, item)) {
+                spawn_pickup(&mut commands, catalog, previous, 1, transform.translation);
+            }
+            equip_writer.send(EquipItemEvent {
+                entity: event.player,
+                slot: *slot,
+                item: Some(item.clone()),
+            });
+            info!("{} equipped {}", name, definition.name);
+
+            pickup.count -= 1;
+            if pickup.count == 0 {
+                commands.entity(entity).despawn_recursive();
+            }
+            continue;
+        }
+
+        let left = inventory.add(&pickup.item, pickup.count, definition.stack);
+        let taken = pickup.count - left;
+        if taken > 0 {
+            info!("{} picked up {} x{}", name, definition.name, taken);
+        }
+
+        pickup.count = left;
+        if left == 0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::player::ClassDefinition;
+use crate::{
+    AnimationSetCache, ClassCache, GameState, ItemCache, RoomCache, WeaponCache, MODEL_MANIFESTS,
+};
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::utils::get_short_name;
+use bevy_asset_loader::prelude::*;
+use iyes_progress::prelude::*;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrackedCollections>()
+            .add_systems(
+                OnEnter(GameState::PreLoad),
+                (
+                    spawn_loading_screen,
+                    track_collection::<AnimationSetCache>,
+                    track_collection::<ClassCache>,
+                    track_collection::<WeaponCache>,
+                    track_collection::<ItemCache>,
+                    track_collection::<RoomCache>,
+                ),
+            )
+            .add_systems(
+                Update,
+                update_loading_bar.run_if(in_state(GameState::PreLoad)),
+            )
+            .add_systems(OnExit(GameState::PreLoad), despawn::<LoadingScreen>)
+            .add_systems(OnEnter(GameState::LoadFailed), spawn_error_screen)
+            .add_systems(OnEnter(GameState::Load), despawn::<LoadingCamera>);
+    }
+}
+
+const BACKGROUND: Color = Color::rgb(0.08, 0.07, 0.1);
+const TEXT: Color = Color::rgb(0.75, 0.72, 0.68);
+const BAR: Color = Color::rgb(1.0, 0.8, 0.35);
+const ERROR: Color = Color::rgb(0.9, 0.35, 0.3);
+
+/// Shows the loading and error screens until the game camera is spawned in `Load`.
+#[derive(Component)]
+struct LoadingCamera;
+
+#[derive(Component)]
+struct LoadingScreen;
+
+/// The filled part of the loading bar.
+#[derive(Component)]
+struct LoadingBar;
+
+/// The handles of each typed asset collection, by collection name, to tell
+/// which files failed when loading does.
+#[derive(Resource, Default)]
+struct TrackedCollections(Vec<(String, Vec<HandleUntyped>)>);
+
+/// Asks for the same files as the loading state does, which hands back the
+/// handles it is already loading.
+fn track_collection<A: AssetCollection>(world: &mut World) {
+    let handles = A::load(world);
+    world
+        .resource_mut::<TrackedCollections>()
+        .0
+        .push((get_short_name(std::any::type_name::<A>()), handles));
+}
+
+fn despawn<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn screen_root() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(24.0),
+            ..default()
+        },
+        background_color: BACKGROUND.into(),
+        ..default()
+    }
+}
+
+fn text(value: impl Into<String>, font_size: f32, color: Color) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font_size,
+            color,
+            ..default()
+        },
+    )
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands.spawn((
+        Name::from("Loading Camera"),
+        LoadingCamera,
+        Camera2dBundle::default(),
+    ));
+    commands
+        .spawn((Name::from("Loading Screen"), LoadingScreen, screen_root()))
+        .with_children(|parent| {
+            parent.spawn(text("Loading", 32.0, TEXT));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(400.0),
+                        height: Val::Px(16.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: TEXT.into(),
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn((
+                        LoadingBar,
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BAR.into(),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn update_loading_bar(
+    progress: Option<Res<ProgressCounter>>,
+    mut bar_query: Query<&mut Style, With<LoadingBar>>,
+) {
+    let Some(progress) = progress else {
+        return;
+    };
+    let progress = progress.progress();
+    let fraction = if progress.total == 0 {
+        0.0
+    } else {
+        progress.done as f32 / progress.total as f32
+    };
+
+    for mut style in &mut bar_query {
+        style.width = Val::Percent(fraction * 100.0);
+    }
+}
+
+/// Every manifest or manifest entry, collection file, and file a class
+/// depends on that failed to load.
+fn failed_assets(
+    asset_server: &AssetServer,
+    manifests: &Assets<StandardDynamicAssetCollection>,
+    collections: &TrackedCollections,
+    classes: &Assets<ClassDefinition>,
+) -> Vec<String> {
+    let mut failed = Vec::new();
+
+    for manifest in MODEL_MANIFESTS {
+        if asset_server.get_load_state(manifest) == LoadState::Failed {
+            failed.push(format!("{} could not be read", manifest));
+            continue;
+        }
+        let handle = asset_server.get_handle::<StandardDynamicAssetCollection, _>(manifest);
+        let Some(collection) = manifests.get(&handle) else {
+            continue;
+        };
+
+        let mut entries: Vec<_> = collection.0.iter().collect();
+        entries.sort_by(|(first, _), (second, _)| first.cmp(second));
+        for (key, asset) in entries {
+            let paths = match asset {
+                StandardDynamicAsset::File { path } => vec![path],
+                StandardDynamicAsset::Files { paths } => paths.iter().collect(),
+                _ => continue,
+            };
+            for path in paths {
+                if asset_server.get_load_state(path.as_str()) == LoadState::Failed {
+                    failed.push(format!("\"{}\" in {}: {}", key, manifest, path));
+                }
+            }
+        }
+    }
+
+    let failed_path = |handle: HandleId| {
+        if asset_server.get_load_state(handle) != LoadState::Failed {
+            return None;
+        }
+        let path = asset_server.get_handle_path(handle)?;
+        Some(path.path().display().to_string())
+    };
+
+    for (collection, handles) in &collections.0 {
+        for handle in handles {
+            if let Some(path) = failed_path(handle.id()) {
+                failed.push(format!("{}: {}", collection, path));
+            }
+        }
+    }
+
+    let mut classes: Vec<_> = classes.iter().map(|(_, class)| class).collect();
+    classes.sort_by(|first, second| first.name.cmp(&second.name));
+    for class in classes {
+        let dependencies = [
+            ("model", class.model.id()),
+            ("animations", class.animations.id()),
+            ("abilities", class.abilities.id()),
+        ];
+        for (field, handle) in dependencies {
+            if let Some(path) = failed_path(handle) {
+                failed.push(format!("{} class {}: {}", class.name, field, path));
+            }
+        }
+    }
+
+    failed
+}
+
+fn spawn_error_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    manifests: Res<Assets<StandardDynamicAssetCollection>>,
+    collections: Res<TrackedCollections>,
+    classes: Res<Assets<ClassDefinition>>,
+) {
+    let failed = failed_assets(&asset_server, &manifests, &collections, &classes);
+    for line in &failed {
+        error!("Failed to load {}", line);
+    }
+
+    commands
+        .spawn((Name::from("Load Error Screen"), screen_root()))
+        .with_children(|parent| {
+            parent.spawn(text("Some assets failed to load", 32.0, ERROR));
+            if failed.is_empty() {
+                parent.spawn(text(
+                    "No asset reports a failed load, check the log for details",
+                    20.0,
+                    TEXT,
+                ));
+            }
+            for line in &failed {
+                parent.spawn(text(line.clone(), 20.0, TEXT));
+            }
+        });
+}
@@ -1,44 +1,170 @@
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
 use bevy_asset_loader::prelude::*;
 use bevy_inspector_egui::quick::*;
 use bevy_rapier3d::prelude::*;
+use iyes_progress::prelude::*;
+use std::time::Duration;
 
 mod animation;
 mod camera;
+mod combat;
+mod enemy;
+mod equipment;
+mod fade;
 mod input;
+mod interaction;
+mod inventory;
+mod loading;
+mod menu;
+mod navigation;
 mod physics;
 mod player;
+mod ron_config;
 mod room_builder;
+mod save;
+mod settings;
+mod solvability;
+mod transition;
+mod trap;
 
 #[derive(States, PartialEq, Eq, Debug, Clone, Hash, Default)]
 pub enum GameState {
     #[default]
     PreLoad,
+    /// Something in `PreLoad` failed to load, shown on the error screen.
+    LoadFailed,
     Load,
     MainMenu,
     Gameplay,
+    /// Gameplay frozen under the pause menu. Nothing is torn down on the way
+    /// in or out, so the game carries on where it stopped.
+    Paused,
+    /// Fading between rooms while the next one is built.
     Transition,
 }
 
-#[derive(Default)]
+/// The asset manifests behind the keyed models in `StructureCache`,
+/// `PropCache` and `CharacterCache`.
+pub const MODEL_MANIFESTS: [&str; 2] = [
+    "manifests/static_models.assets.ron",
+    "manifests/character_models.assets.ron",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum CharacterId {
-    #[default]
-    Player,
+    Adventurer(player::ClassId),
     SkeletonWarrior,
     SkeletonMage,
+    SkeletonArcher,
+    SkeletonMinion,
+}
+
+impl Default for CharacterId {
+    fn default() -> Self {
+        CharacterId::Adventurer(player::ClassId::default())
+    }
 }
 
 #[derive(Component)]
 pub struct Animated;
 
 #[derive(Resource, AssetCollection)]
-pub struct PlayerAnimationCache {
-    #[asset(key = "idle")]
-    idle: Handle<AnimationClip>,
-    #[asset(key = "interact")]
-    interact: Handle<AnimationClip>,
-    #[asset(key = "run")]
-    run: Handle<AnimationClip>,
+#[cfg_attr(test, derive(Default))]
+pub struct AnimationSetCache {
+    #[asset(path = "animations/skeleton_warrior.animset.ron")]
+    skeleton_warrior: Handle<animation::AnimationSet>,
+    #[asset(path = "animations/skeleton_mage.animset.ron")]
+    skeleton_mage: Handle<animation::AnimationSet>,
+    #[asset(path = "animations/skeleton_archer.animset.ron")]
+    skeleton_archer: Handle<animation::AnimationSet>,
+    #[asset(path = "animations/skeleton_minion.animset.ron")]
+    skeleton_minion: Handle<animation::AnimationSet>,
+}
+
+#[derive(Resource, AssetCollection)]
+#[cfg_attr(test, derive(Default))]
+pub struct ClassCache {
+    #[asset(path = "classes/rogue.class.ron")]
+    rogue: Handle<player::ClassDefinition>,
+    #[asset(path = "classes/knight.class.ron")]
+    knight: Handle<player::ClassDefinition>,
+    #[asset(path = "classes/mage.class.ron")]
+    mage: Handle<player::ClassDefinition>,
+    #[asset(path = "classes/barbarian.class.ron")]
+    barbarian: Handle<player::ClassDefinition>,
+}
+
+impl ClassCache {
+    pub fn get(&self, class_id: player::ClassId) -> &Handle<player::ClassDefinition> {
+        match class_id {
+            player::ClassId::Rogue => &self.rogue,
+            player::ClassId::Knight => &self.knight,
+            player::ClassId::Mage => &self.mage,
+            player::ClassId::Barbarian => &self.barbarian,
+        }
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+pub struct WeaponCache {
+    #[asset(path = "weapons/sword_1handed.weapon.ron")]
+    sword_1handed: Handle<combat::Weapon>,
+    #[asset(path = "weapons/axe_2handed.weapon.ron")]
+    axe_2handed: Handle<combat::Weapon>,
+    #[asset(path = "weapons/dagger.weapon.ron")]
+    dagger: Handle<combat::Weapon>,
+    #[asset(path = "weapons/crossbow_1handed.weapon.ron")]
+    crossbow_1handed: Handle<combat::Weapon>,
+    #[asset(path = "weapons/crossbow_2handed.weapon.ron")]
+    crossbow_2handed: Handle<combat::Weapon>,
+    #[asset(path = "weapons/wand.weapon.ron")]
+    wand: Handle<combat::Weapon>,
+    #[asset(path = "weapons/staff.weapon.ron")]
+    staff: Handle<combat::Weapon>,
+}
+
+impl WeaponCache {
+    pub fn get(&self, weapon_id: combat::WeaponId) -> &Handle<combat::Weapon> {
+        match weapon_id {
+            combat::WeaponId::Sword1Handed => &self.sword_1handed,
+            combat::WeaponId::Axe2Handed => &self.axe_2handed,
+            combat::WeaponId::Dagger => &self.dagger,
+            combat::WeaponId::Crossbow1Handed => &self.crossbow_1handed,
+            combat::WeaponId::Crossbow2Handed => &self.crossbow_2handed,
+            combat::WeaponId::Wand => &self.wand,
+            combat::WeaponId::Staff => &self.staff,
+        }
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+pub struct ItemCache {
+    #[asset(path = "items/dungeon.items.ron")]
+    catalog: Handle<inventory::ItemCatalog>,
+}
+
+#[derive(Resource, AssetCollection)]
+pub struct RoomCache {
+    #[asset(path = "rooms/entrance.room.ron")]
+    entrance: Handle<room_builder::RoomFile>,
+    #[asset(path = "rooms/crypt.room.ron")]
+    crypt: Handle<room_builder::RoomFile>,
+}
+
+impl RoomCache {
+    pub fn get(&self, room_id: &str) -> Option<&Handle<room_builder::RoomFile>> {
+        match room_id {
+            "entrance" => Some(&self.entrance),
+            "crypt" => Some(&self.crypt),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Resource, AssetCollection)]
@@ -49,16 +175,103 @@ pub struct StructureCache {
     wall_corner: Handle<Scene>,
     #[asset(key = "door")]
     door: Handle<Scene>,
+    #[asset(key = "gated_door")]
+    gated_door: Handle<Scene>,
     #[asset(key = "multi_corner")]
     multi_corner: Handle<Scene>,
     #[asset(key = "t_split")]
     t_split: Handle<Scene>,
+    #[asset(key = "spikes")]
+    spikes: Handle<Scene>,
+    #[asset(key = "grate")]
+    grate: Handle<Scene>,
+    #[asset(key = "grate_open")]
+    grate_open: Handle<Scene>,
+    #[asset(key = "pit")]
+    pit: Handle<Scene>,
+}
+
+#[derive(Resource, AssetCollection)]
+pub struct PropCache {
+    #[asset(key = "chest")]
+    chest: Handle<Scene>,
+    #[asset(key = "lever")]
+    lever: Handle<Scene>,
+    #[asset(key = "checkpoint")]
+    checkpoint: Handle<Scene>,
 }
 
 #[derive(Resource, AssetCollection)]
+#[cfg_attr(test, derive(Default))]
 pub struct CharacterCache {
-    #[asset(key = "player")]
-    player: Handle<Scene>,
+    #[asset(key = "skeleton_warrior")]
+    skeleton_warrior: Handle<Scene>,
+    #[asset(key = "skeleton_mage")]
+    skeleton_mage: Handle<Scene>,
+    #[asset(key = "skeleton_archer")]
+    skeleton_archer: Handle<Scene>,
+    #[asset(key = "skeleton_minion")]
+    skeleton_minion: Handle<Scene>,
+    #[asset(key = "skeleton_warrior_broken")]
+    skeleton_warrior_broken: Handle<Scene>,
+    #[asset(key = "skeleton_mage_broken")]
+    skeleton_mage_broken: Handle<Scene>,
+    #[asset(key = "skeleton_archer_broken")]
+    skeleton_archer_broken: Handle<Scene>,
+    #[asset(key = "skeleton_minion_broken")]
+    skeleton_minion_broken: Handle<Scene>,
+}
+
+/// Looks up the model and animation set for any character. Adventurers take
+/// theirs from their class definition, everyone else from the caches.
+#[derive(SystemParam)]
+pub struct CharacterAssets<'w> {
+    characters: Res<'w, CharacterCache>,
+    animation_sets: Res<'w, AnimationSetCache>,
+    classes: Res<'w, ClassCache>,
+    class_definitions: Res<'w, Assets<player::ClassDefinition>>,
+}
+
+impl CharacterAssets<'_> {
+    pub fn class(&self, class_id: player::ClassId) -> Option<&player::ClassDefinition> {
+        self.class_definitions.get(self.classes.get(class_id))
+    }
+
+    pub fn scene(&self, character_id: CharacterId) -> Option<&Handle<Scene>> {
+        match character_id {
+            CharacterId::Adventurer(class_id) => self.class(class_id).map(|class| &class.model),
+            CharacterId::SkeletonWarrior => Some(&self.characters.skeleton_warrior),
+            CharacterId::SkeletonMage => Some(&self.characters.skeleton_mage),
+            CharacterId::SkeletonArcher => Some(&self.characters.skeleton_archer),
+            CharacterId::SkeletonMinion => Some(&self.characters.skeleton_minion),
+        }
+    }
+
+    /// The smashed up model a character leaves behind, if it has one.
+    pub fn broken_scene(&self, character_id: CharacterId) -> Option<&Handle<Scene>> {
+        match character_id {
+            CharacterId::Adventurer(_) => None,
+            CharacterId::SkeletonWarrior => Some(&self.characters.skeleton_warrior_broken),
+            CharacterId::SkeletonMage => Some(&self.characters.skeleton_mage_broken),
+            CharacterId::SkeletonArcher => Some(&self.characters.skeleton_archer_broken),
+            CharacterId::SkeletonMinion => Some(&self.characters.skeleton_minion_broken),
+        }
+    }
+
+    pub fn animation_set(
+        &self,
+        character_id: CharacterId,
+    ) -> Option<&Handle<animation::AnimationSet>> {
+        match character_id {
+            CharacterId::Adventurer(class_id) => {
+                self.class(class_id).map(|class| &class.animations)
+            }
+            CharacterId::SkeletonWarrior => Some(&self.animation_sets.skeleton_warrior),
+            CharacterId::SkeletonMage => Some(&self.animation_sets.skeleton_mage),
+            CharacterId::SkeletonArcher => Some(&self.animation_sets.skeleton_archer),
+            CharacterId::SkeletonMinion => Some(&self.animation_sets.skeleton_minion),
+        }
+    }
 }
 
 #[derive(Default, Reflect)]
@@ -78,49 +291,86 @@ pub struct CameraController {
     pub bounds: CameraBounds,
 }
 
-fn rotate_camera(mut camera_query: Query<(&mut Transform, &CameraController)>) {
-    for (mut transform, controller) in &mut camera_query {
-        transform.rotation.x = -1.0 * controller.x_angle.to_radians();
+fn main() {
+    let replay_mode = input::ReplayMode::from_args();
+    let mut app = App::new();
+
+    if replay_mode.is_headless() {
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    wgpu_settings: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    },
+                })
+                .disable::<WinitPlugin>(),
+            ScheduleRunnerPlugin::run_loop(Duration::ZERO),
+        ));
+    } else {
+        app.add_plugins((DefaultPlugins, WorldInspectorPlugin::default()));
     }
-}
 
-fn main() {
-    App::new()
-        .add_plugins((DefaultPlugins, WorldInspectorPlugin::default()))
+    app.insert_resource(replay_mode)
         .add_plugins((
             animation::AnimationPlugin,
+            camera::CameraPlugin,
+            combat::CombatPlugin,
+            enemy::EnemyPlugin,
+            equipment::EquipmentPlugin,
+            navigation::NavigationPlugin,
             room_builder::RoomBuilderPlugin,
             physics::PhysicsPlugin,
             player::PlayerPlugin,
             input::InputPlugin,
+            inventory::InventoryPlugin,
+            menu::MenuPlugin,
+            interaction::InteractionPlugin,
+            trap::TrapPlugin,
+            fade::FadePlugin,
+        ))
+        .add_plugins((
+            save::SavePlugin,
+            settings::SettingsPlugin,
+            loading::LoadingPlugin,
+            transition::TransitionPlugin,
+            ProgressPlugin::new(GameState::PreLoad).continue_to(GameState::Load),
         ))
         .add_state::<GameState>()
         .register_type::<CameraController>()
         .register_type::<CameraBounds>()
-        .add_loading_state(LoadingState::new(GameState::PreLoad).continue_to_state(GameState::Load))
-        .add_collection_to_loading_state::<_, PlayerAnimationCache>(GameState::PreLoad)
+        .add_loading_state(
+            LoadingState::new(GameState::PreLoad)
+                .on_failure_continue_to_state(GameState::LoadFailed),
+        )
+        .add_collection_to_loading_state::<_, AnimationSetCache>(GameState::PreLoad)
+        .add_collection_to_loading_state::<_, ClassCache>(GameState::PreLoad)
+        .add_collection_to_loading_state::<_, WeaponCache>(GameState::PreLoad)
+        .add_collection_to_loading_state::<_, ItemCache>(GameState::PreLoad)
+        .add_collection_to_loading_state::<_, RoomCache>(GameState::PreLoad)
         .add_collection_to_loading_state::<_, StructureCache>(GameState::PreLoad)
+        .add_collection_to_loading_state::<_, PropCache>(GameState::PreLoad)
         .add_collection_to_loading_state::<_, CharacterCache>(GameState::PreLoad)
         .add_dynamic_collection_to_loading_state::<_, StandardDynamicAssetCollection>(
             GameState::PreLoad,
-            "manifests/static_models.assets.ron",
-        )
-        .add_dynamic_collection_to_loading_state::<_, StandardDynamicAssetCollection>(
-            GameState::PreLoad,
-            "manifests/player_animations.assets.ron",
+            MODEL_MANIFESTS[0],
         )
         .add_dynamic_collection_to_loading_state::<_, StandardDynamicAssetCollection>(
             GameState::PreLoad,
-            "manifests/character_models.assets.ron",
+            MODEL_MANIFESTS[1],
         )
         .add_systems(OnEnter(GameState::Load), startup)
-        .add_systems(Update, move_to_gameplay.run_if(in_state(GameState::Load)))
-        .add_systems(Update, rotate_camera.run_if(in_state(GameState::Gameplay)))
+        .add_systems(Update, move_to_main_menu.run_if(in_state(GameState::Load)))
         .run();
 }
 
-fn move_to_gameplay(mut next_state: ResMut<NextState<GameState>>) {
-    next_state.set(GameState::Gameplay);
+fn move_to_main_menu(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::MainMenu);
 }
 
 fn startup(mut commands: Commands) {
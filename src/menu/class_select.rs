@@ -0,0 +1,85 @@
+use super::{
+    class_name, spawn_hint, spawn_menu_screen, MenuInput, MenuScreen, BACKGROUND, HIGHLIGHT, TEXT,
+};
+use crate::player::{ClassId, SelectedClass};
+use crate::{CharacterAssets, GameState};
+use bevy::prelude::*;
+
+/// One entry in the class picker.
+#[derive(Component)]
+pub struct ClassOption(ClassId);
+
+pub fn spawn_class_select(mut commands: Commands, characters: CharacterAssets) {
+    spawn_menu_screen(
+        &mut commands,
+        "Class Select",
+        "Choose your adventurer",
+        BACKGROUND,
+    )
+    .with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    column_gap: Val::Px(48.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                for class_id in ClassId::ALL {
+                    row.spawn((
+                        ClassOption(class_id),
+                        TextBundle::from_section(
+                            class_name(&characters, class_id),
+                            TextStyle {
+                                font_size: 32.0,
+                                color: TEXT,
+                                ..default()
+                            },
+                        ),
+                    ));
+                }
+            });
+        spawn_hint(
+            parent,
+            "Left / Right to choose, Enter or (A) to start, Esc or (B) to go back",
+        );
+    });
+}
+
+pub fn browse_classes(
+    input: MenuInput,
+    mut selected: ResMut<SelectedClass>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    let current = selected.0.index() as usize;
+    let count = ClassId::ALL.len();
+    if input.left() {
+        selected.0 = ClassId::ALL[(current + count - 1) % count];
+    }
+    if input.right() {
+        selected.0 = ClassId::ALL[(current + 1) % count];
+    }
+    if input.confirm() {
+        next_state.set(GameState::Gameplay);
+    } else if input.back() {
+        next_screen.set(MenuScreen::Title);
+    }
+}
+
+pub fn highlight_selected_class(
+    selected: Res<SelectedClass>,
+    mut option_query: Query<(&ClassOption, &mut Text)>,
+) {
+    for (option, mut text) in &mut option_query {
+        let color = if option.0 == selected.0 {
+            HIGHLIGHT
+        } else {
+            TEXT
+        };
+        for section in &mut text.sections {
+            section.style.color = color;
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::input::live_input;
+use crate::player::{ClassId, SelectedClass};
+use crate::{CharacterAssets, GameState};
+use bevy::ecs::system::{EntityCommands, SystemParam};
+use bevy::prelude::*;
+
+mod class_select;
+mod pause;
+mod settings;
+mod title;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedClass>()
+            .init_resource::<MenuCursor>()
+            .add_state::<MenuScreen>()
+            .add_systems(OnEnter(GameState::MainMenu), open_title.run_if(live_input))
+            .add_systems(OnEnter(MenuScreen::Title), title::spawn_title)
+            .add_systems(
+                OnEnter(MenuScreen::ClassSelect),
+                class_select::spawn_class_select,
+            )
+            .add_systems(OnEnter(MenuScreen::Settings), settings::spawn_settings)
+            .add_systems(OnExit(MenuScreen::Title), despawn_menu::<MenuRoot>)
+            .add_systems(OnExit(MenuScreen::ClassSelect), despawn_menu::<MenuRoot>)
+            .add_systems(OnExit(MenuScreen::Settings), despawn_menu::<MenuRoot>)
+            .add_systems(
+                Update,
+                (
+                    title::choose_title_entry.run_if(in_state(MenuScreen::Title)),
+                    (
+                        class_select::browse_classes,
+                        class_select::highlight_selected_class,
+                    )
+                        .chain()
+                        .run_if(in_state(MenuScreen::ClassSelect)),
+                    (settings::change_settings, settings::update_setting_labels)
+                        .chain()
+                        .run_if(in_state(MenuScreen::Settings)),
+                    pause::choose_pause_entry.run_if(in_state(GameState::Paused)),
+                    highlight_menu_entries,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                pause::pause_game
+                    .run_if(in_state(GameState::Gameplay))
+                    .run_if(live_input),
+            )
+            .add_systems(
+                OnEnter(GameState::Paused),
+                (pause::freeze_gameplay, pause::spawn_pause_menu),
+            )
+            .add_systems(
+                OnExit(GameState::Paused),
+                (pause::unfreeze_gameplay, despawn_menu::<MenuRoot>),
+            )
+            .add_systems(
+                OnExit(GameState::MainMenu),
+                (close_menus, despawn_menu::<MenuRoot>),
+            );
+    }
+}
+
+const BACKGROUND: Color = Color::rgb(0.08, 0.07, 0.1);
+const TEXT: Color = Color::rgb(0.75, 0.72, 0.68);
+const HIGHLIGHT: Color = Color::rgb(1.0, 0.8, 0.35);
+const DISABLED: Color = Color::rgb(0.35, 0.33, 0.32);
+
+/// The screen shown while in `GameState::MainMenu`.
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum MenuScreen {
+    #[default]
+    Closed,
+    Title,
+    ClassSelect,
+    Settings,
+}
+
+/// The top node of a menu screen, despawned with everything under it when the screen closes.
+#[derive(Component)]
+pub struct MenuRoot;
+
+/// One line of a vertical menu, picked with the `MenuCursor`.
+#[derive(Component)]
+pub struct MenuEntry(usize);
+
+/// Shown greyed out, and does nothing when chosen.
+#[derive(Component)]
+pub struct Disabled;
+
+/// The highlighted line of whichever menu is open.
+#[derive(Resource, Default, Debug)]
+pub struct MenuCursor(usize);
+
+impl MenuCursor {
+    /// Moves up or down a menu `count` lines long, wrapping around at the ends.
+    fn browse(&mut self, input: &MenuInput, count: usize) {
+        if count == 0 {
+            return;
+        }
+        if input.up() {
+            self.0 = (self.0 + count - 1) % count;
+        }
+        if input.down() {
+            self.0 = (self.0 + 1) % count;
+        }
+    }
+}
+
+/// Menus are driven straight from devices, since nobody owns an input map
+/// until a player has spawned.
+#[derive(SystemParam)]
+pub struct MenuInput<'w> {
+    keys: Res<'w, Input<KeyCode>>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+}
+
+impl MenuInput<'_> {
+    fn pressed(&self, key_codes: &[KeyCode], button_type: GamepadButtonType) -> bool {
+        self.keys.any_just_pressed(key_codes.iter().copied())
+            || self
+                .gamepad_buttons
+                .get_just_pressed()
+                .any(|button| button.button_type == button_type)
+    }
+
+    fn up(&self) -> bool {
+        self.pressed(&[KeyCode::Up, KeyCode::W], GamepadButtonType::DPadUp)
+    }
+
+    fn down(&self) -> bool {
+        self.pressed(&[KeyCode::Down, KeyCode::S], GamepadButtonType::DPadDown)
+    }
+
+    fn left(&self) -> bool {
+        self.pressed(&[KeyCode::Left, KeyCode::A], GamepadButtonType::DPadLeft)
+    }
+
+    fn right(&self) -> bool {
+        self.pressed(&[KeyCode::Right, KeyCode::D], GamepadButtonType::DPadRight)
+    }
+
+    fn confirm(&self) -> bool {
+        self.pressed(&[KeyCode::Return, KeyCode::Space], GamepadButtonType::South)
+    }
+
+    fn back(&self) -> bool {
+        self.pressed(&[KeyCode::Escape, KeyCode::Back], GamepadButtonType::East)
+    }
+}
+
+fn open_title(mut next_screen: ResMut<NextState<MenuScreen>>) {
+    next_screen.set(MenuScreen::Title);
+}
+
+fn close_menus(mut next_screen: ResMut<NextState<MenuScreen>>) {
+    next_screen.set(MenuScreen::Closed);
+}
+
+fn despawn_menu<T: Component>(mut commands: Commands, root_query: Query<Entity, With<T>>) {
+    for entity in &root_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Spawns a full screen column for a menu, headed by `title`.
+fn spawn_menu_screen<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    name: &str,
+    title: &str,
+    background: Color,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut root = commands.spawn((
+        Name::from(name.to_string()),
+        MenuRoot,
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(32.0),
+                ..default()
+            },
+            background_color: background.into(),
+            ..default()
+        },
+    ));
+    root.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            title,
+            TextStyle {
+                font_size: 48.0,
+                color: TEXT,
+                ..default()
+            },
+        ));
+    });
+    root
+}
+
+fn spawn_entries(parent: &mut ChildBuilder, entries: &[(&str, bool)], font_size: f32) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(font_size * 0.4),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|column| {
+            for (index, (label, enabled)) in entries.iter().enumerate() {
+                let mut entry = column.spawn((
+                    MenuEntry(index),
+                    TextBundle::from_section(
+                        *label,
+                        TextStyle {
+                            font_size,
+                            color: TEXT,
+                            ..default()
+                        },
+                    ),
+                ));
+                if !enabled {
+                    entry.insert(Disabled);
+                }
+            }
+        });
+}
+
+fn spawn_hint(parent: &mut ChildBuilder, hint: &str) {
+    parent.spawn(TextBundle::from_section(
+        hint,
+        TextStyle {
+            font_size: 20.0,
+            color: TEXT,
+            ..default()
+        },
+    ));
+}
+
+fn highlight_menu_entries(
+    cursor: Res<MenuCursor>,
+    mut entry_query: Query<(&MenuEntry, &mut Text, Option<&Disabled>)>,
+) {
+    for (entry, mut text, disabled) in &mut entry_query {
+        let color = match (entry.0 == cursor.0, disabled.is_some()) {
+            (true, _) => HIGHLIGHT,
+            (false, true) => DISABLED,
+            (false, false) => TEXT,
+        };
+        for section in &mut text.sections {
+            section.style.color = color;
+        }
+    }
+}
+
+fn class_name(characters: &CharacterAssets, class_id: ClassId) -> String {
+    characters
+        .class(class_id)
+        .map(|class| class.name.clone())
+        .unwrap_or_else(|| format!("{:?}", class_id))
+}
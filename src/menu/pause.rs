@@ -0,0 +1,89 @@
+use super::{spawn_entries, spawn_hint, spawn_menu_screen, MenuCursor, MenuInput};
+use crate::input::PlayerAction;
+use crate::player::Player;
+use crate::save::save_game;
+use crate::GameState;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+const RESUME: usize = 0;
+const SAVE: usize = 1;
+const QUIT: usize = 2;
+const ENTRIES: usize = 3;
+
+/// Dims the game behind the pause menu rather than hiding it.
+const PAUSE_BACKGROUND: Color = Color::rgba(0.08, 0.07, 0.1, 0.75);
+
+/// Whether a player pressed pause. Only joined players' devices count, so a
+/// gamepad pressing start to join doesn't pause or resume the game.
+fn pause_pressed(player_query: &Query<&ActionState<PlayerAction>, With<Player>>) -> bool {
+    player_query
+        .iter()
+        .any(|action_state| action_state.just_pressed(PlayerAction::Pause))
+}
+
+/// Any player can pause, including one waiting to respawn.
+pub fn pause_game(
+    player_query: Query<&ActionState<PlayerAction>, With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if pause_pressed(&player_query) {
+        next_state.set(GameState::Paused);
+    }
+}
+
+/// Stops the physics simulation and the clock, so nothing moves, animates or
+/// times out while the game is paused. Gameplay systems only run in
+/// `GameState::Gameplay` and stop on their own.
+pub fn freeze_gameplay(mut rapier_config: ResMut<RapierConfiguration>, mut time: ResMut<Time>) {
+    rapier_config.physics_pipeline_active = false;
+    time.pause();
+}
+
+pub fn unfreeze_gameplay(mut rapier_config: ResMut<RapierConfiguration>, mut time: ResMut<Time>) {
+    rapier_config.physics_pipeline_active = true;
+    time.unpause();
+}
+
+pub fn spawn_pause_menu(mut commands: Commands, mut cursor: ResMut<MenuCursor>) {
+    cursor.0 = RESUME;
+
+    spawn_menu_screen(&mut commands, "Pause Menu", "Paused", PAUSE_BACKGROUND).with_children(
+        |parent| {
+            spawn_entries(
+                parent,
+                &[("Resume", true), ("Save game", true), ("Quit", true)],
+                32.0,
+            );
+            spawn_hint(parent, "Enter or (A) to confirm, Esc or (Start) to resume");
+        },
+    );
+}
+
+pub fn choose_pause_entry(
+    mut commands: Commands,
+    input: MenuInput,
+    player_query: Query<&ActionState<PlayerAction>, With<Player>>,
+    mut cursor: ResMut<MenuCursor>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_writer: EventWriter<AppExit>,
+) {
+    if input.back() || pause_pressed(&player_query) {
+        next_state.set(GameState::Gameplay);
+        return;
+    }
+
+    cursor.browse(&input, ENTRIES);
+    if !input.confirm() {
+        return;
+    }
+
+    match cursor.0 {
+        RESUME => next_state.set(GameState::Gameplay),
+        SAVE => commands.add(save_game),
+        QUIT => exit_writer.send(AppExit),
+        _ => (),
+    }
+}
@@ -0,0 +1,181 @@
+use super::{
+    spawn_hint, spawn_menu_screen, MenuCursor, MenuEntry, MenuInput, MenuScreen, BACKGROUND, TEXT,
+};
+use crate::input::{InputBindings, PlayerAction, RebindRequest, RebindTarget, Rebinding};
+use crate::settings::Settings;
+use bevy::prelude::*;
+
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SettingRow {
+    Volume,
+    Fullscreen,
+    Vsync,
+    AntiAliasing,
+    Binding(RebindTarget),
+    Back,
+}
+
+const ROWS: [SettingRow; 16] = [
+    SettingRow::Volume,
+    SettingRow::Fullscreen,
+    SettingRow::Vsync,
+    SettingRow::AntiAliasing,
+    SettingRow::Binding(RebindTarget::MoveUp),
+    SettingRow::Binding(RebindTarget::MoveDown),
+    SettingRow::Binding(RebindTarget::MoveLeft),
+    SettingRow::Binding(RebindTarget::MoveRight),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::Jump)),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::Attack)),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::HeavyAttack)),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::Dodge)),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::Interact)),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::UseItem)),
+    SettingRow::Binding(RebindTarget::Action(PlayerAction::Pause)),
+    SettingRow::Back,
+];
+
+/// Keeps a settings line's text in step with the value it shows.
+#[derive(Component)]
+pub struct SettingLabel(SettingRow);
+
+pub fn spawn_settings(mut commands: Commands, mut cursor: ResMut<MenuCursor>) {
+    cursor.0 = 0;
+
+    spawn_menu_screen(&mut commands, "Settings", "Settings", BACKGROUND).with_children(|parent| {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|column| {
+                for (index, row) in ROWS.into_iter().enumerate() {
+                    column.spawn((
+                        MenuEntry(index),
+                        SettingLabel(row),
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 22.0,
+                                color: TEXT,
+                                ..default()
+                            },
+                        ),
+                    ));
+                }
+            });
+        spawn_hint(
+            parent,
+            "Left / Right to change, Enter or (A) to rebind, Esc or (B) to go back",
+        );
+    });
+}
+
+pub fn change_settings(
+    input: MenuInput,
+    mut cursor: ResMut<MenuCursor>,
+    mut settings: ResMut<Settings>,
+    rebinding: Res<Rebinding>,
+    mut rebind_writer: EventWriter<RebindRequest>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    // The press that finishes a rebind shouldn't also work the menu.
+    if rebinding.target().is_some() || rebinding.is_changed() {
+        return;
+    }
+
+    cursor.browse(&input, ROWS.len());
+    let row = ROWS[cursor.0.min(ROWS.len() - 1)];
+    let step = match (input.left(), input.right()) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+    let toggled = step != 0.0 || input.confirm();
+
+    match row {
+        SettingRow::Volume if step != 0.0 => {
+            settings.volume = (settings.volume + step * VOLUME_STEP).clamp(0.0, 1.0);
+        }
+        SettingRow::Fullscreen if toggled => settings.fullscreen = !settings.fullscreen,
+        SettingRow::Vsync if toggled => settings.vsync = !settings.vsync,
+        SettingRow::AntiAliasing if toggled => settings.anti_aliasing = !settings.anti_aliasing,
+        SettingRow::Binding(target) if input.confirm() => {
+            rebind_writer.send(RebindRequest { target });
+        }
+        SettingRow::Back if input.confirm() => {
+            settings.save();
+            next_screen.set(MenuScreen::Title);
+        }
+        _ => (),
+    }
+
+    if input.back() {
+        settings.save();
+        next_screen.set(MenuScreen::Title);
+    }
+}
+
+pub fn update_setting_labels(
+    settings: Res<Settings>,
+    bindings: Res<InputBindings>,
+    rebinding: Res<Rebinding>,
+    mut label_query: Query<(&SettingLabel, &mut Text)>,
+) {
+    let on_off = |on: bool| if on { "On" } else { "Off" };
+
+    for (label, mut text) in &mut label_query {
+        let value = match label.0 {
+            SettingRow::Volume => format!("Volume   < {:.0}% >", settings.volume * 100.0),
+            SettingRow::Fullscreen => format!("Fullscreen   {}", on_off(settings.fullscreen)),
+            SettingRow::Vsync => format!("VSync   {}", on_off(settings.vsync)),
+            SettingRow::AntiAliasing => {
+                format!("Anti-aliasing   {}", on_off(settings.anti_aliasing))
+            }
+            SettingRow::Binding(target) if rebinding.target() == Some(target) => {
+                format!("{}   press a button...", target_name(target))
+            }
+            SettingRow::Binding(target) => {
+                format!(
+                    "{}   {}",
+                    target_name(target),
+                    binding_label(&bindings, target)
+                )
+            }
+            SettingRow::Back => "Back".to_string(),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+fn target_name(target: RebindTarget) -> String {
+    match target {
+        RebindTarget::Action(action) => format!("{:?}", action),
+        RebindTarget::MoveUp => "Move up".to_string(),
+        RebindTarget::MoveDown => "Move down".to_string(),
+        RebindTarget::MoveLeft => "Move left".to_string(),
+        RebindTarget::MoveRight => "Move right".to_string(),
+    }
+}
+
+fn binding_label(bindings: &InputBindings, target: RebindTarget) -> String {
+    match target {
+        RebindTarget::Action(action) => [false, true]
+            .into_iter()
+            .filter_map(|gamepad| bindings.label(action, gamepad))
+            .collect::<Vec<_>>()
+            .join(" / "),
+        RebindTarget::MoveUp => format!("{:?}", bindings.move_keys.up),
+        RebindTarget::MoveDown => format!("{:?}", bindings.move_keys.down),
+        RebindTarget::MoveLeft => format!("{:?}", bindings.move_keys.left),
+        RebindTarget::MoveRight => format!("{:?}", bindings.move_keys.right),
+    }
+}
@@ -0,0 +1,79 @@
+use super::{
+    spawn_entries, spawn_hint, spawn_menu_screen, MenuCursor, MenuInput, MenuScreen, BACKGROUND,
+};
+use crate::player::SelectedClass;
+use crate::room_builder::CurrentRoom;
+use crate::save::{Dungeon, LoadedSave, SaveFile, WaitingPlayers, SAVE_PATH};
+use crate::GameState;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+const NEW_GAME: usize = 0;
+const CONTINUE: usize = 1;
+const SETTINGS: usize = 2;
+const QUIT: usize = 3;
+const ENTRIES: usize = 4;
+
+pub fn spawn_title(mut commands: Commands, mut cursor: ResMut<MenuCursor>) {
+    let can_continue = SaveFile::exists();
+    cursor.0 = if can_continue { CONTINUE } else { NEW_GAME };
+
+    spawn_menu_screen(&mut commands, "Title", "Soul of Adventure", BACKGROUND).with_children(
+        |parent| {
+            spawn_entries(
+                parent,
+                &[
+                    ("New game", true),
+                    ("Continue", can_continue),
+                    ("Settings", true),
+                    ("Quit", true),
+                ],
+                32.0,
+            );
+            spawn_hint(parent, "Up / Down to choose, Enter or (A) to confirm");
+        },
+    );
+}
+
+pub fn choose_title_entry(
+    mut commands: Commands,
+    input: MenuInput,
+    mut cursor: ResMut<MenuCursor>,
+    mut selected: ResMut<SelectedClass>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut exit_writer: EventWriter<AppExit>,
+) {
+    cursor.browse(&input, ENTRIES);
+    if !input.confirm() {
+        return;
+    }
+
+    match cursor.0 {
+        NEW_GAME => {
+            commands.insert_resource(CurrentRoom::default());
+            commands.insert_resource(Dungeon::default());
+            commands.remove_resource::<WaitingPlayers>();
+            next_screen.set(MenuScreen::ClassSelect);
+        }
+        CONTINUE => {
+            if !SaveFile::exists() {
+                return;
+            }
+            match SaveFile::read() {
+                Ok(save) => {
+                    if let Some(player) = save.first_player() {
+                        selected.0 = player.class;
+                    }
+                    commands.insert_resource(CurrentRoom(save.room.clone()));
+                    commands.insert_resource(LoadedSave(save));
+                    next_state.set(GameState::Gameplay);
+                }
+                Err(error) => warn!("Could not load {}: {}", SAVE_PATH, error),
+            }
+        }
+        SETTINGS => next_screen.set(MenuScreen::Settings),
+        QUIT => exit_writer.send(AppExit),
+        _ => (),
+    }
+}
@@ -0,0 +1,455 @@
+use crate::interaction::Door;
+use crate::room_builder::{Location, Room, RoomTile, TileType};
+use crate::GameState;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (sync_door_tiles, update_nav_paths)
+                .chain()
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+const WAYPOINT_REACHED: f32 = 0.8;
+/// Fraction of a tile between samples when checking a straight line for walls.
+const SIGHT_STEP: f32 = 0.1;
+
+/// The walkable grid of the room that is currently built. Its doors follow
+/// the `Door` entities: a door tile counts as locked while the door is shut,
+/// so agents don't walk into the blocker of a closed door.
+#[derive(Resource)]
+pub struct NavGrid {
+    room: Room,
+}
+
+impl NavGrid {
+    pub fn new(mut room: Room) -> Self {
+        for y in 0..room.height() {
+            for x in 0..room.width() {
+                if matches!(room.get(x, y), Some(TileType::Door { .. })) {
+                    room.set(x, y, Some(TileType::Door { locked: true }));
+                }
+            }
+        }
+        NavGrid { room }
+    }
+
+    /// World-space waypoints from `from` to `to`, or `None` when `to` can't be reached.
+    pub fn path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.room.location_at(from)?;
+        let goal = self.room.location_at(to)?;
+        let tiles = self.room.find_path(start, goal)?;
+        Some(self.room.waypoints(&tiles))
+    }
+
+    pub fn location_at(&self, translation: Vec3) -> Option<Location> {
+        self.room.location_at(translation)
+    }
+
+    pub fn tile_at(&self, translation: Vec3) -> Option<TileType> {
+        let location = self.room.location_at(translation)?;
+        self.room.get(location.x, location.y)
+    }
+}
+
+impl TileType {
+    pub fn is_walkable(self) -> bool {
+        matches!(self, Self::Empty | Self::Door { locked: false })
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct OpenTile {
+    cost: usize,
+    estimate: usize,
+    index: usize,
+}
+
+impl Ord for OpenTile {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.cost + other.estimate)
+            .cmp(&(self.cost + self.estimate))
+            .then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl PartialOrd for OpenTile {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Room {
+    pub fn is_walkable(&self, x: usize, y: usize) -> bool {
+        x < self.width() && y < self.height() && self.get(x, y).map_or(false, TileType::is_walkable)
+    }
+
+    /// The tile under a world-space position, the inverse of `get_translation`.
+    pub fn location_at(&self, translation: Vec3) -> Option<Location> {
+        let local = (translation - self.origin) / 4.0;
+        let (x, y) = (local.x.round(), local.z.round());
+        if x < 0.0 || y < 0.0 || x as usize >= self.width() || y as usize >= self.height() {
+            return None;
+        }
+        Some(Location {
+            x: x as usize,
+            y: y as usize,
+        })
+    }
+
+    /// A* over walkable tiles, crossing doors to reach other chambers.
+    pub fn find_path(&self, start: Location, goal: Location) -> Option<Vec<Location>> {
+        if !self.is_walkable(start.x, start.y) || !self.is_walkable(goal.x, goal.y) {
+            return None;
+        }
+
+        let width = self.width();
+        let index = |location: Location| location.y * width + location.x;
+        let estimate =
+            |location: Location| location.x.abs_diff(goal.x) + location.y.abs_diff(goal.y);
+
+        let mut costs = vec![usize::MAX; self.total_tiles()];
+        let mut came_from: Vec<Option<usize>> = vec![None; self.total_tiles()];
+        let mut open = BinaryHeap::new();
+
+        costs[index(start)] = 0;
+        open.push(OpenTile {
+            cost: 0,
+            estimate: estimate(start),
+            index: index(start),
+        });
+
+        while let Some(OpenTile {
+            cost,
+            index: current,
+            ..
+        }) = open.pop()
+        {
+            if current == index(goal) {
+                let mut path = vec![goal];
+                let mut step = current;
+                while let Some(previous) = came_from[step] {
+                    path.push(Location {
+                        x: previous % width,
+                        y: previous / width,
+                    });
+                    step = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if cost > costs[current] {
+                continue;
+            }
+
+            let (x, y) = (current % width, current / width);
+            let neighbours = [
+                (x.checked_sub(1), Some(y)),
+                (Some(x + 1), Some(y)),
+                (Some(x), y.checked_sub(1)),
+                (Some(x), Some(y + 1)),
+            ];
+            for (nx, ny) in neighbours {
+                let (Some(nx), Some(ny)) = (nx, ny) else {
+                    continue;
+                };
+                if !self.is_walkable(nx, ny) {
+                    continue;
+                }
+
+                let next = Location { x: nx, y: ny };
+                let next_cost = cost + 1;
+                if next_cost < costs[index(next)] {
+                    costs[index(next)] = next_cost;
+                    came_from[index(next)] = Some(current);
+                    open.push(OpenTile {
+                        cost: next_cost,
+                        estimate: estimate(next),
+                        index: index(next),
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Drops every tile that can be skipped by walking straight past it, and
+    /// turns what is left into world-space points.
+    pub fn waypoints(&self, path: &[Location]) -> Vec<Vec3> {
+        let mut kept: Vec<Location> = Vec::new();
+        let mut anchor = match path.first() {
+            Some(first) => *first,
+            None => return Vec::new(),
+        };
+
+        for window in path.windows(2) {
+            let (previous, next) = (window[0], window[1]);
+            if !self.has_clear_line(anchor, next) {
+                kept.push(previous);
+                anchor = previous;
+            }
+        }
+        if let Some(last) = path.last() {
+            kept.push(*last);
+        }
+
+        kept.iter()
+            .map(|location| self.get_translation(location.x, location.y))
+            .collect()
+    }
+
+    fn has_clear_line(&self, from: Location, to: Location) -> bool {
+        let start = Vec2::new(from.x as f32, from.y as f32);
+        let offset = Vec2::new(to.x as f32, to.y as f32) - start;
+        let steps = (offset.length() / SIGHT_STEP).ceil() as usize;
+
+        (0..=steps).all(|step| {
+            let point = start + offset * (step as f32 / steps.max(1) as f32);
+            self.is_walkable(point.x.round() as usize, point.y.round() as usize)
+        })
+    }
+}
+
+/// Something that walks the `NavGrid`: set `goal` and steer towards `next_waypoint`.
+#[derive(Component, Default)]
+pub struct NavAgent {
+    pub goal: Option<Vec3>,
+    waypoints: VecDeque<Vec3>,
+    goal_location: Option<Location>,
+}
+
+impl NavAgent {
+    pub fn next_waypoint(&self) -> Option<Vec3> {
+        self.waypoints.front().copied()
+    }
+}
+
+fn sync_door_tiles(
+    nav_grid: Option<ResMut<NavGrid>>,
+    door_query: Query<(&Door, &RoomTile), Changed<Door>>,
+) {
+    let Some(mut nav_grid) = nav_grid else {
+        return;
+    };
+    for (door, tile) in &door_query {
+        let (x, y) = tile.0;
+        let current = nav_grid.room.get(x, y);
+        let wanted = Some(TileType::Door { locked: !door.open });
+        // Only touch the grid when a door really moved, agents re-plan when it changes.
+        if matches!(current, Some(TileType::Door { .. })) && current != wanted {
+            nav_grid.room.set(x, y, wanted);
+        }
+    }
+}
+
+/// Re-plans when the goal moves to another tile and drops waypoints as they are reached.
+pub fn update_nav_paths(
+    nav_grid: Option<Res<NavGrid>>,
+    mut agent_query: Query<(&Transform, &mut NavAgent)>,
+) {
+    let Some(nav_grid) = nav_grid else {
+        return;
+    };
+
+    for (transform, mut agent) in &mut agent_query {
+        let Some(goal) = agent.goal else {
+            agent.waypoints.clear();
+            agent.goal_location = None;
+            continue;
+        };
+
+        let goal_location = nav_grid.location_at(goal);
+        if goal_location != agent.goal_location || nav_grid.is_changed() {
+            agent.goal_location = goal_location;
+            agent.waypoints = nav_grid
+                .path(transform.translation, goal)
+                .unwrap_or_default()
+                .into();
+            // The last tile centre stands in for the goal itself.
+            if agent.waypoints.pop_back().is_some() {
+                agent.waypoints.push_back(goal);
+            }
+        }
+
+        while let Some(waypoint) = agent.next_waypoint() {
+            let offset = waypoint - transform.translation;
+            if Vec2::new(offset.x, offset.z).length() > WAYPOINT_REACHED {
+                break;
+            }
+            agent.waypoints.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interaction::DoorBundle;
+    use crate::room_builder::RoomFile;
+
+    fn room(layout: &str) -> Room {
+        let file: RoomFile = ron::from_str(&format!("(layout: {:?})", layout)).unwrap();
+        Room::from_file(&file, Vec3::ZERO)
+    }
+
+    fn at(x: usize, y: usize) -> Location {
+        Location { x, y }
+    }
+
+    const BEND: &str = "
+        xxxxx
+        x...x
+        xxx.x
+        x...x
+        xxxxx
+    ";
+
+    #[test]
+    fn no_path_through_walls() {
+        let room = room(
+            "
+            xxxxxxx
+            x..x..x
+            x..x..x
+            xxxxxxx
+            ",
+        );
+        assert_eq!(room.find_path(at(1, 1), at(4, 1)), None);
+        assert_eq!(room.find_path(at(1, 1), at(3, 1)), None);
+    }
+
+    #[test]
+    fn path_goes_around_walls() {
+        let room = room(BEND);
+        let path = room.find_path(at(1, 1), at(1, 3)).unwrap();
+
+        assert_eq!(
+            path,
+            vec![
+                at(1, 1),
+                at(2, 1),
+                at(3, 1),
+                at(3, 2),
+                at(3, 3),
+                at(2, 3),
+                at(1, 3)
+            ]
+        );
+        for step in path.windows(2) {
+            let distance = step[0].x.abs_diff(step[1].x) + step[0].y.abs_diff(step[1].y);
+            assert_eq!(distance, 1);
+        }
+    }
+
+    #[test]
+    fn path_crosses_open_doors_only() {
+        let open = room(
+            "
+            xxxxx
+            x.d.x
+            xxxxx
+            ",
+        );
+        assert!(open.find_path(at(1, 1), at(3, 1)).is_some());
+
+        let mut locked = open.clone();
+        locked.set(2, 1, Some(TileType::Door { locked: true }));
+        assert_eq!(locked.find_path(at(1, 1), at(3, 1)), None);
+    }
+
+    #[test]
+    fn doors_are_walkable_only_while_open() {
+        let room = room(
+            "
+            xxxxx
+            x.d.x
+            xxxxx
+            ",
+        );
+        let (from, to) = (room.get_translation(1, 1), room.get_translation(3, 1));
+        let mut app = App::new();
+        app.insert_resource(NavGrid::new(room))
+            .add_systems(Update, sync_door_tiles);
+        let door = DoorBundle::new(Entity::PLACEHOLDER, None, Some("key".to_string())).door;
+        let door = app.world.spawn((door, RoomTile((2, 1)))).id();
+        let set_door = |app: &mut App, key: Option<&str>, open: bool| {
+            let mut state = app.world.get_mut::<Door>(door).unwrap();
+            state.key = key.map(str::to_string);
+            state.open = open;
+            app.update();
+            app.world.resource::<NavGrid>().path(from, to).is_some()
+        };
+
+        assert!(!set_door(&mut app, Some("key"), false));
+        assert!(!set_door(&mut app, None, false));
+        assert!(set_door(&mut app, None, true));
+        assert!(!set_door(&mut app, None, false));
+    }
+
+    #[test]
+    fn smoothing_keeps_the_corners() {
+        let room = room(BEND);
+        let path = room.find_path(at(1, 1), at(1, 3)).unwrap();
+
+        assert_eq!(
+            room.waypoints(&path),
+            vec![
+                room.get_translation(3, 1),
+                room.get_translation(3, 3),
+                room.get_translation(1, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn smoothing_never_cuts_through_a_wall() {
+        let room = room(
+            "
+            xxxxxxx
+            x.....x
+            x.xxx.x
+            x.x...x
+            x.x.xxx
+            x.....x
+            xxxxxxx
+            ",
+        );
+        // Either way round takes two turns.
+        let path = room.find_path(at(1, 1), at(3, 3)).unwrap();
+        let waypoints: Vec<Location> = room
+            .waypoints(&path)
+            .into_iter()
+            .map(|point| room.location_at(point).unwrap())
+            .collect();
+
+        assert_eq!(waypoints.len(), 3);
+        for pair in waypoints.windows(2) {
+            assert!(room.has_clear_line(pair[0], pair[1]));
+        }
+        assert!(room.has_clear_line(path[0], waypoints[0]));
+    }
+
+    #[test]
+    fn smoothing_walks_straight_across_open_floor() {
+        let room = room(
+            "
+            xxxxx
+            x...x
+            x...x
+            x...x
+            xxxxx
+            ",
+        );
+        let path = room.find_path(at(1, 1), at(3, 3)).unwrap();
+        assert_eq!(room.waypoints(&path), vec![room.get_translation(3, 3)]);
+    }
+}
@@ -1,4 +1,5 @@
 use super::{Character, Direction, Grounded, Landing, Momentum, Speed};
+use crate::animation::{AnimationState, AnimationSystems};
 use crate::camera::MainCamera;
 use crate::input::PlayerAction;
 use crate::player::Player;
@@ -14,6 +15,7 @@ impl Plugin for LateralMovementPlugin {
         app.add_systems(
             Update,
             (rotate_to_direction, handle_speed, apply_momentum)
+                .after(AnimationSystems::UpdateState)
                 .run_if(in_state(GameState::Gameplay)),
         );
     }
@@ -45,9 +47,14 @@ pub fn rotate_to_direction(
 
 fn handle_speed(
     time: Res<Time>,
-    mut character_query: Query<(&Direction, &mut Momentum, &mut Speed), With<Grounded>>,
+    mut character_query: Query<(&Character, &Direction, &mut Momentum, &mut Speed), With<Grounded>>,
 ) {
-    for (direction, mut momentum, mut speed) in &mut character_query {
+    for (character, direction, mut momentum, mut speed) in &mut character_query {
+        // A dodge carries the player at its own speed until the roll ends.
+        if character.state == AnimationState::Dodging {
+            continue;
+        }
+
         if direction.is_any() {
             speed.accelerate(&time);
             momentum.set(speed.current());
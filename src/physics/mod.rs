@@ -1,4 +1,5 @@
 use crate::animation::AnimationState;
+use crate::CharacterId;
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
@@ -13,7 +14,10 @@ impl Plugin for PhysicsPlugin {
             RapierPhysicsPlugin::<NoUserData>::default(),
             RapierDebugRenderPlugin::default(),
         ))
-        .add_plugins((lateral::LateralMovementPlugin,))
+        .add_plugins((
+            lateral::LateralMovementPlugin,
+            vertical::VerticalMovementPlugin,
+        ))
         .register_type::<Speed>()
         .register_type::<Direction>()
         .register_type::<Momentum>()
@@ -35,6 +39,16 @@ pub struct Speed {
 }
 
 impl Speed {
+    pub fn new(base_speed: f32, top_speed: f32) -> Self {
+        Speed {
+            base_speed,
+            current_speed: base_speed,
+            base_top_speed: top_speed,
+            top_speed,
+            ..default()
+        }
+    }
+
     pub fn reset(&mut self) {
         self.current_speed = self.base_speed;
         self.top_speed = self.base_top_speed;
@@ -166,9 +180,19 @@ pub struct Grounded;
 
 #[derive(Component, Default)]
 pub struct Character {
+    pub id: CharacterId,
     pub state: AnimationState,
 }
 
+impl Character {
+    pub fn new(id: CharacterId) -> Self {
+        Character {
+            id,
+            state: AnimationState::default(),
+        }
+    }
+}
+
 #[derive(Bundle)]
 pub struct MovementBundle {
     pub rigidbody: RigidBody,
@@ -1 +1,57 @@
+use super::{Character, Grounded};
+use crate::animation::AnimationSystems;
+use crate::GameState;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
 pub struct VerticalMovementPlugin;
+
+impl Plugin for VerticalMovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            check_ground
+                .before(AnimationSystems::UpdateState)
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+/// How far above the feet the ground probe starts, so it isn't already
+/// inside the floor.
+const PROBE_START: f32 = 0.1;
+/// How far below the feet still counts as standing on something.
+const PROBE_REACH: f32 = 0.15;
+
+/// Keeps `Grounded` on the characters with something solid under their feet.
+fn check_ground(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    character_query: Query<(Entity, &Transform, Option<&Grounded>), With<Character>>,
+) {
+    for (entity, transform, grounded) in &character_query {
+        let filter = QueryFilter::new()
+            .exclude_sensors()
+            .exclude_rigid_body(entity);
+        let is_grounded = rapier_context
+            .cast_ray(
+                transform.translation + Vec3::Y * PROBE_START,
+                Vec3::NEG_Y,
+                PROBE_START + PROBE_REACH,
+                true,
+                filter,
+            )
+            .is_some();
+
+        match (grounded.is_some(), is_grounded) {
+            (false, true) => {
+                commands.entity(entity).insert(Grounded);
+            }
+            (true, false) => {
+                commands.entity(entity).remove::<Grounded>();
+            }
+            _ => (),
+        }
+    }
+}
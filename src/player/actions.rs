@@ -0,0 +1,277 @@
+use super::{ComboChain, ComboList, Player, PlayerClass};
+use crate::animation::{ActionAnimationEvent, AnimationLock, AnimationState, AnimationSystems};
+use crate::combat::{AttackEvent, Dead, Health};
+use crate::input::{buffer_player_actions, InputBuffer, PlayerAction};
+use crate::interaction::{InteractKind, InteractionFocus};
+use crate::physics::{Character, Grounded, Momentum};
+use crate::{CharacterAssets, GameState};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use std::time::Duration;
+
+pub struct PlayerActionsPlugin;
+
+impl Plugin for PlayerActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerIntentEvent>().add_systems(
+            Update,
+            (read_player_actions, start_intent_actions)
+                .chain()
+                .after(buffer_player_actions)
+                .before(AnimationSystems::UpdateState)
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+/// Forward speed of a roll, held for as long as `AnimationState::Dodging` lasts.
+const DODGE_SPEED: f32 = 18.0;
+const JUMP_SPEED: f32 = 6.0;
+/// Dodging makes the player untouchable for the start of the roll.
+const DODGE_INVULNERABLE: f32 = 0.3;
+/// How long a press waits for the player to be free to act on it.
+const BUFFER_WINDOW: Duration = Duration::from_millis(250);
+const ACTION_INPUTS: [PlayerAction; 4] = [
+    PlayerAction::Dodge,
+    PlayerAction::Jump,
+    PlayerAction::Interact,
+    PlayerAction::UseItem,
+];
+const ATTACK_INPUTS: [PlayerAction; 2] = [PlayerAction::Attack, PlayerAction::HeavyAttack];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlayerIntent {
+    Jump,
+    Attack {
+        attack: u8,
+        duration: f32,
+        power: f32,
+    },
+    /// Using whatever the player is focused on.
+    Interact {
+        target: Entity,
+        kind: InteractKind,
+    },
+    Dodge,
+    UseItem,
+}
+
+impl PlayerIntent {
+    fn from_action(action: PlayerAction, focus: Option<&InteractionFocus>) -> Option<Self> {
+        match action {
+            PlayerAction::Jump => Some(PlayerIntent::Jump),
+            PlayerAction::Interact => focus
+                .and_then(InteractionFocus::target)
+                .map(|(target, kind)| PlayerIntent::Interact { target, kind }),
+            PlayerAction::Dodge => Some(PlayerIntent::Dodge),
+            PlayerAction::UseItem => Some(PlayerIntent::UseItem),
+            PlayerAction::Move
+            | PlayerAction::Attack
+            | PlayerAction::HeavyAttack
+            | PlayerAction::Pause => None,
+        }
+    }
+
+    /// The animation state an intent plays, and how long it holds the character.
+    fn action_animation(self) -> Option<(AnimationState, f32)> {
+        match self {
+            PlayerIntent::Attack {
+                attack, duration, ..
+            } => Some((AnimationState::Attacking(attack), duration)),
+            PlayerIntent::Interact { kind, .. } => Some(kind.animation()),
+            PlayerIntent::Dodge => Some((AnimationState::Dodging, 0.4)),
+            PlayerIntent::UseItem => Some((AnimationState::Throwing, 1.2)),
+            PlayerIntent::Jump => None,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct PlayerIntentEvent {
+    pub player: Entity,
+    pub intent: PlayerIntent,
+}
+
+/// Turns buffered presses into intents once the player is free to act on them.
+/// Attacks advance the player's combo chain, and a dodge may cancel an attack.
+fn read_player_actions(
+    time: Res<Time>,
+    characters: CharacterAssets,
+    combo_lists: Res<Assets<ComboList>>,
+    mut intent_writer: EventWriter<PlayerIntentEvent>,
+    mut player_query: Query<
+        (
+            Entity,
+            &Character,
+            &PlayerClass,
+            &mut InputBuffer,
+            &mut ComboChain,
+            Option<&AnimationLock>,
+            Option<&Grounded>,
+            Option<&InteractionFocus>,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
+) {
+    let now = time.elapsed();
+
+    for (entity, character, class, mut buffer, mut chain, lock, grounded, focus) in
+        &mut player_query
+    {
+        let combos = characters
+            .class(class.0)
+            .and_then(|class| combo_lists.get(&class.abilities));
+        let mut intents = Vec::new();
+
+        if lock.map_or(false, |lock| !lock.finished()) {
+            if matches!(character.state, AnimationState::Attacking(_))
+                && buffer.consume(PlayerAction::Dodge, now, BUFFER_WINDOW)
+            {
+                chain.inputs.clear();
+                intents.push(PlayerIntent::Dodge);
+            }
+        } else {
+            chain.wait(
+                time.delta_seconds(),
+                combos.map_or(0.0, |combos| combos.chain_window),
+            );
+
+            // Presses stay buffered while airborne so a jump can fire on landing.
+            let action = if grounded.is_some() {
+                buffer.consume_any(&ACTION_INPUTS, now, BUFFER_WINDOW)
+            } else {
+                None
+            };
+
+            if let Some(intent) = action.and_then(|action| PlayerIntent::from_action(action, focus))
+            {
+                chain.inputs.clear();
+                intents.push(intent);
+            } else if let (Some(combos), Some(input)) = (
+                combos,
+                buffer.consume_any(&ATTACK_INPUTS, now, BUFFER_WINDOW),
+            ) {
+                let step = match combos.next_step(&chain.inputs, input) {
+                    Some(step) => Some(step),
+                    None => {
+                        chain.inputs.clear();
+                        combos.next_step(&[], input)
+                    }
+                };
+                if let Some(step) = step {
+                    chain.advance(input);
+                    intents.push(PlayerIntent::Attack {
+                        attack: step.attack,
+                        duration: step.duration,
+                        power: step.power,
+                    });
+                }
+            }
+        }
+
+        for intent in intents {
+            intent_writer.send(PlayerIntentEvent {
+                player: entity,
+                intent,
+            });
+        }
+    }
+}
+
+fn start_intent_actions(
+    mut intent_reader: EventReader<PlayerIntentEvent>,
+    mut action_writer: EventWriter<ActionAnimationEvent>,
+    mut attack_writer: EventWriter<AttackEvent>,
+    mut player_query: Query<
+        (&mut Momentum, &mut Velocity, &mut Health),
+        (With<Player>, With<Grounded>),
+    >,
+) {
+    for event in intent_reader.iter() {
+        let Ok((mut momentum, mut velocity, mut health)) = player_query.get_mut(event.player)
+        else {
+            continue;
+        };
+
+        match event.intent {
+            PlayerIntent::Jump => velocity.linvel.y = JUMP_SPEED,
+            PlayerIntent::Dodge => {
+                momentum.set(DODGE_SPEED);
+                health.make_invulnerable(DODGE_INVULNERABLE);
+            }
+            PlayerIntent::Attack { attack, power, .. } => attack_writer.send(AttackEvent {
+                attacker: event.player,
+                attack,
+                power,
+            }),
+            _ => (),
+        }
+
+        if let Some((state, duration)) = event.intent.action_animation() {
+            action_writer.send(ActionAnimationEvent {
+                entity: event.player,
+                state,
+                duration,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::ClassDefinition;
+    use crate::{AnimationSetCache, CharacterCache, CharacterId, ClassCache};
+    use leafwing_input_manager::prelude::ActionState;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), PlayerActionsPlugin))
+            .add_asset::<ComboList>()
+            .add_asset::<ClassDefinition>()
+            .init_resource::<CharacterCache>()
+            .init_resource::<AnimationSetCache>()
+            .init_resource::<ClassCache>()
+            .add_event::<ActionAnimationEvent>()
+            .add_event::<AttackEvent>()
+            .add_state::<GameState>()
+            .insert_resource(NextState(Some(GameState::Gameplay)))
+            .add_systems(
+                Update,
+                buffer_player_actions.run_if(in_state(GameState::Gameplay)),
+            );
+        app
+    }
+
+    #[test]
+    fn buffered_press_becomes_an_intent() {
+        let mut app = app();
+        let mut action_state = ActionState::<PlayerAction>::default();
+        action_state.press(PlayerAction::Dodge);
+        let player = app
+            .world
+            .spawn((
+                Player,
+                Character::new(CharacterId::default()),
+                PlayerClass(Default::default()),
+                action_state,
+                InputBuffer::default(),
+                ComboChain::default(),
+                Grounded,
+                Momentum::default(),
+                Velocity::default(),
+                Health::new(100.0),
+            ))
+            .id();
+
+        app.update();
+
+        let events = app.world.resource::<Events<PlayerIntentEvent>>();
+        let intents: Vec<_> = events
+            .get_reader()
+            .iter(events)
+            .map(|event| (event.player, event.intent))
+            .collect();
+        assert_eq!(intents, vec![(player, PlayerIntent::Dodge)]);
+    }
+}
@@ -0,0 +1,124 @@
+use super::ComboList;
+use crate::animation::AnimationSet;
+use crate::equipment::Loadout;
+use crate::physics::Speed;
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum ClassId {
+    #[default]
+    Rogue,
+    Knight,
+    Mage,
+    Barbarian,
+}
+
+impl ClassId {
+    pub const ALL: [ClassId; 4] = [
+        ClassId::Rogue,
+        ClassId::Knight,
+        ClassId::Mage,
+        ClassId::Barbarian,
+    ];
+
+    pub fn index(self) -> u8 {
+        ClassId::ALL
+            .iter()
+            .position(|class| *class == self)
+            .unwrap_or_default() as u8
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        ClassId::ALL.get(index as usize).copied()
+    }
+}
+
+/// The class a player is playing as.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerClass(pub ClassId);
+
+/// The class picked in the main menu, used for every player that spawns.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct SelectedClass(pub ClassId);
+
+#[derive(Deserialize)]
+struct SpeedDefinition {
+    base: f32,
+    top: f32,
+}
+
+#[derive(Deserialize)]
+struct ClassFile {
+    name: String,
+    model: String,
+    animations: String,
+    abilities: String,
+    speed: SpeedDefinition,
+    #[serde(default)]
+    loadout: Loadout,
+}
+
+/// Everything that sets one playable class apart, loaded from a `class.ron` file.
+#[derive(TypeUuid, TypePath, Debug)]
+#[uuid = "8c3e1a7f-4d92-4b6e-a0f5-2b9d7c6e1f43"]
+pub struct ClassDefinition {
+    pub name: String,
+    pub model: Handle<Scene>,
+    pub animations: Handle<AnimationSet>,
+    /// The combos the class can string together.
+    pub abilities: Handle<ComboList>,
+    pub base_speed: f32,
+    pub top_speed: f32,
+    /// Equipment a new character of this class starts with.
+    pub loadout: Loadout,
+}
+
+impl ClassDefinition {
+    pub fn speed(&self) -> Speed {
+        Speed::new(self.base_speed, self.top_speed)
+    }
+}
+
+#[derive(Default)]
+pub struct ClassDefinitionLoader;
+
+impl AssetLoader for ClassDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file: ClassFile = ron::de::from_bytes(bytes)?;
+            let dependencies: Vec<AssetPath<'static>> = [
+                file.model.as_str(),
+                file.animations.as_str(),
+                file.abilities.as_str(),
+            ]
+            .into_iter()
+            .map(|path| AssetPath::from(path).to_owned())
+            .collect();
+
+            load_context.set_default_asset(
+                LoadedAsset::new(ClassDefinition {
+                    name: file.name,
+                    model: load_context.get_handle(dependencies[0].clone()),
+                    animations: load_context.get_handle(dependencies[1].clone()),
+                    abilities: load_context.get_handle(dependencies[2].clone()),
+                    base_speed: file.speed.base,
+                    top_speed: file.speed.top,
+                    loadout: file.loadout,
+                })
+                .with_dependencies(dependencies),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["class.ron"]
+    }
+}
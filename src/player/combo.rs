@@ -0,0 +1,187 @@
+use crate::input::PlayerAction;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ComboStep {
+    pub input: PlayerAction,
+    pub attack: u8,
+    pub duration: f32,
+    /// Scales the weapon's damage and knockback.
+    #[serde(default = "default_power")]
+    pub power: f32,
+}
+
+fn default_power() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Combo {
+    pub name: String,
+    pub steps: Vec<ComboStep>,
+}
+
+/// Move strings such as attack-attack-heavy, loaded from a `combos.ron` file.
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "2f9d6c41-8b3e-4a57-b0c2-7e1f5a9d3c68"]
+pub struct ComboList {
+    pub chain_window: f32,
+    pub combos: Vec<Combo>,
+}
+
+impl ComboList {
+    /// The step that follows `chain` when `input` is pressed. The first combo
+    /// whose opening matches wins, so a shared prefix plays the same attacks.
+    pub fn next_step(&self, chain: &[PlayerAction], input: PlayerAction) -> Option<&ComboStep> {
+        self.combos.iter().find_map(|combo| {
+            let step = combo.steps.get(chain.len())?;
+            let continues = combo
+                .steps
+                .iter()
+                .zip(chain)
+                .all(|(step, pressed)| step.input == *pressed);
+            (continues && step.input == input).then_some(step)
+        })
+    }
+}
+
+/// The inputs of the combo a player is part way through.
+#[derive(Component, Default)]
+pub struct ComboChain {
+    pub inputs: Vec<PlayerAction>,
+    pub idle: f32,
+}
+
+impl ComboChain {
+    /// Counts `delta` seconds without an attack, and drops the chain once it
+    /// has waited longer than `window` for the next one.
+    pub fn wait(&mut self, delta: f32, window: f32) {
+        self.idle += delta;
+        if self.idle > window {
+            self.inputs.clear();
+        }
+    }
+
+    pub fn advance(&mut self, input: PlayerAction) {
+        self.inputs.push(input);
+        self.idle = 0.0;
+    }
+}
+
+#[derive(Default)]
+pub struct ComboListLoader;
+
+impl AssetLoader for ComboListLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let combos: ComboList = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(combos));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["combos.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMBOS: &str = "(
+        chain_window: 0.3,
+        combos: [
+            (
+                name: \"Reaver\",
+                steps: [
+                    (input: Attack, attack: 3, duration: 1.0),
+                    (input: Attack, attack: 3, duration: 1.0, power: 1.2),
+                    (input: HeavyAttack, attack: 4, duration: 1.3, power: 2.2),
+                ],
+            ),
+            (
+                name: \"Jab\",
+                steps: [
+                    (input: Attack, attack: 0, duration: 0.5),
+                    (input: HeavyAttack, attack: 1, duration: 0.8),
+                ],
+            ),
+            (
+                name: \"Overhead\",
+                steps: [(input: HeavyAttack, attack: 4, duration: 1.3, power: 1.8)],
+            ),
+        ],
+    )";
+
+    fn combos() -> ComboList {
+        ron::from_str(COMBOS).unwrap()
+    }
+
+    fn attack(step: Option<&ComboStep>) -> Option<(u8, f32)> {
+        step.map(|step| (step.attack, step.power))
+    }
+
+    #[test]
+    fn steps_follow_the_chain() {
+        use PlayerAction::{Attack, HeavyAttack};
+        let combos = combos();
+
+        assert_eq!(attack(combos.next_step(&[], Attack)), Some((3, 1.0)));
+        assert_eq!(attack(combos.next_step(&[Attack], Attack)), Some((3, 1.2)));
+        assert_eq!(
+            attack(combos.next_step(&[Attack, Attack], HeavyAttack)),
+            Some((4, 2.2))
+        );
+        assert_eq!(attack(combos.next_step(&[], HeavyAttack)), Some((4, 1.8)));
+    }
+
+    #[test]
+    fn a_shared_opening_branches_on_the_next_input() {
+        use PlayerAction::{Attack, HeavyAttack};
+        let combos = combos();
+
+        // Both Reaver and Jab open with Attack, the first listed plays it.
+        assert_eq!(
+            attack(combos.next_step(&[Attack], HeavyAttack)),
+            Some((1, 1.0))
+        );
+    }
+
+    #[test]
+    fn broken_and_finished_chains_have_no_next_step() {
+        use PlayerAction::{Attack, HeavyAttack};
+        let combos = combos();
+
+        assert!(combos.next_step(&[HeavyAttack], Attack).is_none());
+        assert!(combos.next_step(&[Attack, Attack], Attack).is_none());
+        assert!(combos
+            .next_step(&[Attack, Attack, HeavyAttack], Attack)
+            .is_none());
+    }
+
+    #[test]
+    fn the_chain_waits_out_the_window() {
+        let window = combos().chain_window;
+        let mut chain = ComboChain::default();
+        chain.advance(PlayerAction::Attack);
+
+        chain.wait(window, window);
+        assert_eq!(chain.inputs, vec![PlayerAction::Attack]);
+
+        // Attacking again restarts the wait.
+        chain.advance(PlayerAction::Attack);
+        chain.wait(0.25, window);
+        assert_eq!(chain.inputs.len(), 2);
+
+        chain.wait(0.1, window);
+        assert!(chain.inputs.is_empty());
+    }
+}
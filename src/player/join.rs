@@ -0,0 +1,113 @@
+use super::{spawn_player_character, Player, PlayerSlot, SelectedClass, MAX_PLAYERS};
+use crate::input::{live_input, InputBindings, InputDevices, PlayerAction};
+use crate::save::WaitingPlayers;
+use crate::{CharacterAssets, GameState};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::{ActionState, InputMap};
+use leafwing_input_manager::Actionlike;
+
+pub struct PlayerJoinPlugin;
+
+impl Plugin for PlayerJoinPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            join_from_gamepad
+                .run_if(in_state(GameState::Gameplay))
+                .run_if(live_input),
+        );
+    }
+}
+
+const JOIN_BUTTON: GamepadButtonType = GamepadButtonType::Start;
+const JOIN_OFFSET: Vec3 = Vec3::new(1.5, 0.0, 0.0);
+
+/// Buttons still held from joining are ignored until released, so the start
+/// press doesn't also pause the game.
+fn ignore_held_buttons(action_state: &mut ActionState<PlayerAction>) {
+    for action in PlayerAction::variants() {
+        action_state.consume(action);
+    }
+}
+
+/// Pressing start on a gamepad nobody owns hands it to the first player if
+/// they have none, or else spawns a player for it in the next free slot, next
+/// to the first player. A slot left by a loaded save comes back as the class
+/// it was saved with.
+#[allow(clippy::type_complexity)]
+fn join_from_gamepad(
+    mut commands: Commands,
+    characters: CharacterAssets,
+    bindings: Res<InputBindings>,
+    selected: Res<SelectedClass>,
+    waiting: Option<Res<WaitingPlayers>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut player_query: Query<
+        (
+            &PlayerSlot,
+            &mut InputDevices,
+            &mut InputMap<PlayerAction>,
+            &mut ActionState<PlayerAction>,
+            &Transform,
+        ),
+        With<Player>,
+    >,
+) {
+    let mut taken: Vec<usize> = player_query.iter().map(|(slot, ..)| slot.0).collect();
+
+    for button in gamepad_buttons.get_just_pressed() {
+        if button.button_type != JOIN_BUTTON {
+            continue;
+        }
+        let gamepad = button.gamepad;
+
+        if player_query
+            .iter()
+            .any(|(_, devices, ..)| devices.gamepad == Some(gamepad))
+        {
+            continue;
+        }
+
+        if let Some((_, mut devices, mut input_map, mut action_state, _)) = player_query
+            .iter_mut()
+            .find(|(slot, devices, ..)| slot.0 == 0 && devices.gamepad.is_none())
+        {
+            devices.gamepad = Some(gamepad);
+            *input_map = bindings.input_map(&devices);
+            ignore_held_buttons(&mut action_state);
+            continue;
+        }
+
+        let Some(index) = (0..MAX_PLAYERS).find(|index| !taken.contains(index)) else {
+            continue;
+        };
+        let anchor = player_query
+            .iter()
+            .find(|(slot, ..)| slot.0 == 0)
+            .map(|(.., transform)| transform.translation)
+            .unwrap_or(Vec3::new(5.0, 5.0, 5.0));
+        taken.push(index);
+
+        let Some(player) = spawn_player_character(
+            &mut commands,
+            &characters,
+            &bindings,
+            waiting
+                .as_ref()
+                .and_then(|waiting| waiting.class(index))
+                .unwrap_or(selected.0),
+            PlayerSlot(index),
+            InputDevices {
+                keyboard: false,
+                gamepad: Some(gamepad),
+            },
+            anchor + JOIN_OFFSET * index as f32,
+        ) else {
+            continue;
+        };
+
+        let mut action_state = ActionState::<PlayerAction>::default();
+        ignore_held_buttons(&mut action_state);
+        commands.entity(player).insert(action_state);
+    }
+}
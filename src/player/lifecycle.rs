@@ -0,0 +1,122 @@
+use super::Player;
+use crate::combat::{Dead, Health, DEATH_SECONDS};
+use crate::fade::ScreenFade;
+use crate::{GameState, PropCache};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub struct PlayerLifecyclePlugin;
+
+impl Plugin for PlayerLifecyclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Checkpoint>().add_systems(
+            Update,
+            (reach_checkpoints, start_respawns, respawn_players)
+                .chain()
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+/// Invulnerability after coming back, so nobody respawns into a beating.
+const RESPAWN_GRACE: f32 = 2.0;
+const CHECKPOINT_REACH: f32 = 1.5;
+
+/// Where dead players come back: the room's entry, or the last checkpoint
+/// anyone reached since.
+#[derive(Resource, Default, Debug)]
+pub struct Checkpoint(pub Vec3);
+
+/// A spot in a room that moves the `Checkpoint` when a player walks past it.
+#[derive(Component)]
+pub struct CheckpointSite;
+
+#[derive(Component)]
+struct Respawn {
+    timer: Timer,
+}
+
+pub fn spawn_checkpoint(commands: &mut Commands, props: &PropCache, translation: Vec3) -> Entity {
+    commands
+        .spawn((
+            Name::from("Checkpoint"),
+            CheckpointSite,
+            SceneBundle {
+                scene: props.checkpoint.clone_weak(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Collider::ball(CHECKPOINT_REACH),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+        ))
+        .id()
+}
+
+fn reach_checkpoints(
+    mut collision_reader: EventReader<CollisionEvent>,
+    mut checkpoint: ResMut<Checkpoint>,
+    site_query: Query<&Transform, With<CheckpointSite>>,
+    player_query: Query<(), (With<Player>, Without<Dead>)>,
+) {
+    for event in collision_reader.iter() {
+        let CollisionEvent::Started(first, second, _) = event else {
+            continue;
+        };
+
+        for (site, player) in [(*first, *second), (*second, *first)] {
+            let Ok(transform) = site_query.get(site) else {
+                continue;
+            };
+            let point = transform.translation + Vec3::Y;
+            if player_query.contains(player) && checkpoint.0 != point {
+                checkpoint.0 = point;
+                info!("Checkpoint reached");
+            }
+        }
+    }
+}
+
+fn start_respawns(mut commands: Commands, dead_query: Query<Entity, (With<Player>, Added<Dead>)>) {
+    for entity in &dead_query {
+        commands.entity(entity).insert(Respawn {
+            timer: Timer::from_seconds(DEATH_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+/// Once a dead player has lain there long enough, fades out, brings them back
+/// at the checkpoint and fades in again.
+fn respawn_players(
+    mut commands: Commands,
+    time: Res<Time>,
+    checkpoint: Res<Checkpoint>,
+    mut fade: ResMut<ScreenFade>,
+    mut player_query: Query<(
+        Entity,
+        &mut Respawn,
+        &mut Transform,
+        &mut Health,
+        Option<&mut Velocity>,
+    )>,
+) {
+    for (entity, mut respawn, mut transform, mut health, velocity) in &mut player_query {
+        respawn.timer.tick(time.delta());
+        if !respawn.timer.finished() {
+            continue;
+        }
+        fade.fade_out();
+        if !fade.is_black() {
+            continue;
+        }
+
+        transform.translation = checkpoint.0;
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::zero();
+        }
+        health.revive();
+        health.make_invulnerable(RESPAWN_GRACE);
+        commands.entity(entity).remove::<(Dead, Respawn)>();
+        fade.fade_in();
+    }
+}
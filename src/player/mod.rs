@@ -1,43 +1,123 @@
 use crate::animation::AnimationState;
-use crate::input::{InputListenerBundle, PlayerAction};
-use crate::physics::{Grounded, MovementBundle};
-use crate::{Animated, CharacterCache, GameState};
+use crate::combat::{CombatBundle, Faction};
+use crate::equipment::EquipmentBundle;
+use crate::input::{InputBindings, InputDevices, InputListenerBundle, PlayerAction};
+use crate::interaction::InteractorBundle;
+use crate::inventory::Inventory;
+use crate::physics::{Character, Grounded, MovementBundle};
+use crate::{Animated, CharacterAssets, CharacterId, GameState};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
-mod animation;
+mod actions;
+mod class;
+mod combo;
+mod join;
+mod lifecycle;
 mod movement;
 
+pub use actions::{PlayerIntent, PlayerIntentEvent};
+pub use class::{ClassDefinition, ClassId, PlayerClass, SelectedClass};
+pub use combo::{Combo, ComboChain, ComboList, ComboStep};
+pub use lifecycle::{spawn_checkpoint, Checkpoint};
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
-            animation::PlayerAnimationPlugin,
+            actions::PlayerActionsPlugin,
+            join::PlayerJoinPlugin,
+            lifecycle::PlayerLifecyclePlugin,
             movement::PlayerMovementPlugin,
         ))
-        .add_systems(OnEnter(GameState::Load), spawn_player);
+        .add_asset::<ComboList>()
+        .init_asset_loader::<combo::ComboListLoader>()
+        .add_asset::<ClassDefinition>()
+        .init_asset_loader::<class::ClassDefinitionLoader>()
+        .add_systems(OnExit(GameState::MainMenu), spawn_player);
     }
 }
 
 #[derive(Component)]
 pub struct Player;
 
-fn spawn_player(mut commands: Commands, characters: Res<CharacterCache>) {
-    commands.spawn((
-        Name::from("Player"),
-        Player,
-        Animated,
-        MovementBundle {
-            collider: Collider::capsule(Vec3::Y * 0.5, Vec3::Y * 1.5, 0.5),
-            ..default()
-        },
-        InputListenerBundle::input_map(),
-        SceneBundle {
-            scene: characters.player.clone_weak(),
-            transform: Transform::from_translation(Vec3::new(5.0, 5.0, 5.0)),
-            ..default()
+pub const MAX_PLAYERS: usize = 4;
+const PLAYER_HEALTH: f32 = 100.0;
+/// Invulnerability after each hit a player takes.
+const PLAYER_HIT_GRACE: f32 = 0.8;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerSlot(pub usize);
+
+fn spawn_player(
+    mut commands: Commands,
+    characters: CharacterAssets,
+    bindings: Res<InputBindings>,
+    selected: Res<SelectedClass>,
+    gamepads: Res<Gamepads>,
+) {
+    // The first player also plays on the first gamepad, so someone alone on a
+    // pad doesn't have to join next to an idle keyboard player.
+    spawn_player_character(
+        &mut commands,
+        &characters,
+        &bindings,
+        selected.0,
+        PlayerSlot(0),
+        InputDevices {
+            keyboard: true,
+            gamepad: gamepads.iter().next(),
         },
-        Grounded,
-    ));
+        Vec3::new(5.0, 5.0, 5.0),
+    );
+}
+
+pub fn spawn_player_character(
+    commands: &mut Commands,
+    characters: &CharacterAssets,
+    bindings: &InputBindings,
+    class_id: ClassId,
+    slot: PlayerSlot,
+    devices: InputDevices,
+    translation: Vec3,
+) -> Option<Entity> {
+    let Some(class) = characters.class(class_id) else {
+        warn!("Class {:?} is not loaded, cannot spawn player", class_id);
+        return None;
+    };
+
+    let player = commands
+        .spawn((
+            Name::from(format!("Player {}", slot.0 + 1)),
+            Player,
+            slot,
+            PlayerClass(class_id),
+            Animated,
+            MovementBundle {
+                collider: Collider::capsule(Vec3::Y * 0.5, Vec3::Y * 1.5, 0.5),
+                speed: class.speed(),
+                character: Character::new(CharacterId::Adventurer(class_id)),
+                ..default()
+            },
+            InputListenerBundle::input_map(bindings, devices),
+            ComboChain::default(),
+            CombatBundle::new(
+                PLAYER_HEALTH,
+                Faction::Adventurers,
+                class.loadout.weapon().unwrap_or_default(),
+            )
+            .with_grace(PLAYER_HIT_GRACE),
+            EquipmentBundle::new(class.loadout.clone()),
+            Inventory::default(),
+            InteractorBundle::default(),
+            SceneBundle {
+                scene: class.model.clone_weak(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Grounded,
+        ))
+        .id();
+    Some(player)
 }
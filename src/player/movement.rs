@@ -1,5 +1,6 @@
 use super::{Player, PlayerAction};
 use crate::camera::MainCamera;
+use crate::combat::Dead;
 use crate::physics::{Direction, Grounded};
 use crate::GameState;
 use bevy::prelude::*;
@@ -22,14 +23,19 @@ pub fn set_player_direction(
             &mut Direction,
             Option<&Grounded>,
             &ActionState<PlayerAction>,
+            Option<&Dead>,
         ),
         With<Player>,
     >,
     camera_query: Query<&Transform, With<MainCamera>>,
 ) {
-    let camera_transform = camera_query.single();
-    for (mut direction, grounded, action) in &mut player_query {
-        if grounded.is_some() {
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+    for (mut direction, grounded, action, dead) in &mut player_query {
+        if dead.is_some() {
+            direction.clear();
+        } else if grounded.is_some() {
             direction.set(get_direction_in_camera_space(camera_transform, action));
         } else {
             if direction.is_any() {
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Reads a config file written by `save`, falling back to the defaults when it
+/// is missing or can't be parsed.
+pub fn load<T: DeserializeOwned + Default>(path: &str) -> T {
+    match fs::read_to_string(path) {
+        Ok(contents) => ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Could not parse {}, using defaults: {}", path, error);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Writes `value` as pretty RON, creating the directory on the way. Failures
+/// are only logged, the game carries on with what it has in memory.
+pub fn save<T: Serialize>(path: &str, value: &T) {
+    let contents = match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => contents,
+        Err(error) => {
+            warn!("Could not serialize {}: {}", path, error);
+            return;
+        }
+    };
+
+    if let Some(directory) = Path::new(path).parent() {
+        if let Err(error) = fs::create_dir_all(directory) {
+            warn!("Could not create {}: {}", directory.display(), error);
+            return;
+        }
+    }
+
+    if let Err(error) = fs::write(path, contents) {
+        warn!("Could not write {}: {}", path, error);
+    }
+}
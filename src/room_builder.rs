@@ -1,12 +1,159 @@
-use crate::{GameState, StructureCache};
+use crate::enemy::spawn_enemy;
+use crate::interaction::{spawn_chest, spawn_lever, DoorBundle};
+use crate::inventory::{spawn_pickup, ItemCatalog, ItemStack};
+use crate::navigation::NavGrid;
+use crate::player::{spawn_checkpoint, Checkpoint};
+use crate::solvability::check_dungeon_solvable;
+use crate::trap::{spawn_trap, TrapKind, TrapTrigger};
+use crate::{
+    CharacterAssets, CharacterId, GameState, ItemCache, PropCache, RoomCache, StructureCache,
+};
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::HashMap;
 use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
 
 pub struct RoomBuilderPlugin;
 
 impl Plugin for RoomBuilderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Gameplay), build_a_room);
+        app.add_asset::<RoomFile>()
+            .init_asset_loader::<RoomFileLoader>()
+            .init_resource::<CurrentRoom>()
+            .init_resource::<PreparedRooms>()
+            .add_systems(OnEnter(GameState::Load), check_dungeon)
+            .add_systems(OnExit(GameState::MainMenu), build_a_room);
+    }
+}
+
+/// The room being played, by its id in the `RoomCache`.
+#[derive(Resource, Clone, PartialEq, Eq, Debug)]
+pub struct CurrentRoom(pub String);
+
+impl Default for CurrentRoom {
+    fn default() -> Self {
+        CurrentRoom("entrance".to_string())
+    }
+}
+
+/// Marks what was spawned for the current room, all of which goes when the
+/// players leave it.
+#[derive(Component, Debug)]
+pub struct InRoom;
+
+/// The layout tile a door, item or object was placed at, which ties it back to
+/// the room file when its state is saved and restored.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RoomTile(pub (usize, usize));
+
+fn default_count() -> u32 {
+    1
+}
+
+/// An item lying on the floor of a room, at a tile of the layout.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ItemPlacement {
+    pub item: String,
+    pub at: (usize, usize),
+    #[serde(default = "default_count")]
+    pub count: u32,
+}
+
+/// Settings for the door on a `d` tile. Doors on a `channel` are worked by the
+/// levers sharing it rather than by hand, and doors with a `key` stay locked
+/// until someone carrying that item uses them.
+#[derive(Deserialize, Clone, Debug)]
+pub struct DoorPlacement {
+    pub at: (usize, usize),
+    #[serde(default)]
+    pub channel: Option<u32>,
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum RoomObject {
+    Chest { loot: Vec<ItemStack> },
+    Lever { channel: u32 },
+}
+
+/// A doorway in a room's outer wall leading `to` another room. Players come
+/// into that room at `entry`, or at its own entry tile.
+#[derive(Component, Deserialize, Clone, Debug)]
+pub struct RoomExit {
+    pub at: (usize, usize),
+    pub to: String,
+    #[serde(default)]
+    pub entry: Option<(usize, usize)>,
+}
+
+/// How far outside its doorway an exit's sensor sits, so it is only reached
+/// once the door has been opened and walked through.
+const EXIT_OFFSET: f32 = 1.6;
+
+/// Overrides how the trap on a trap tile is set off.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TrapPlacement {
+    pub at: (usize, usize),
+    pub trigger: TrapTrigger,
+}
+
+/// An interactive object standing on a tile, turned `facing` degrees.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ObjectPlacement {
+    pub object: RoomObject,
+    pub at: (usize, usize),
+    #[serde(default)]
+    pub facing: f32,
+}
+
+/// A room as written in a `room.ron` file: the tile layout plus whatever is placed in it.
+#[derive(Deserialize, TypeUuid, TypePath, Debug)]
+#[uuid = "a7d3f0c2-1e6b-4c58-9f24-3b8e6d1a0c95"]
+pub struct RoomFile {
+    pub layout: String,
+    /// Where the room's top left tile sits in the world.
+    #[serde(default)]
+    pub origin: Vec3,
+    /// Where players come in, defaulting to the first open tile.
+    #[serde(default)]
+    pub entry: Option<(usize, usize)>,
+    #[serde(default)]
+    pub items: Vec<ItemPlacement>,
+    #[serde(default)]
+    pub doors: Vec<DoorPlacement>,
+    #[serde(default)]
+    pub objects: Vec<ObjectPlacement>,
+    #[serde(default)]
+    pub traps: Vec<TrapPlacement>,
+    /// Tiles that become the respawn point when a player walks past them.
+    #[serde(default)]
+    pub checkpoints: Vec<(usize, usize)>,
+    #[serde(default)]
+    pub exits: Vec<RoomExit>,
+}
+
+#[derive(Default)]
+pub struct RoomFileLoader;
+
+impl AssetLoader for RoomFileLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let room: RoomFile = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(room));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["room.ron"]
     }
 }
 
@@ -23,33 +170,203 @@ pub struct ColliderChild {
     pub transform: Transform,
 }
 
-fn build_a_room(mut commands: Commands, structures: Res<StructureCache>) {
-    let room = Room::from_str(
-        "
-            xxxxxxxxxxxxxxxxx
-            x    x     d    x
-            xxxxxxxxddxxxxdxx
-            x    x      x   x
-            xxxxxxxxxxxxxxxdx
-        ",
-        Vec3::ZERO,
-    );
+/// Everything needed to lay out a room and fill it.
+#[derive(SystemParam)]
+pub struct RoomAssets<'w> {
+    structures: Res<'w, StructureCache>,
+    props: Res<'w, PropCache>,
+    characters: CharacterAssets<'w>,
+    rooms: Res<'w, RoomCache>,
+    room_files: Res<'w, Assets<RoomFile>>,
+    item_cache: Res<'w, ItemCache>,
+    catalogs: Res<'w, Assets<ItemCatalog>>,
+}
+
+impl RoomAssets<'_> {
+    /// Lays out a room from its file, at the origin the file gives it.
+    pub fn room(&self, room_id: &str) -> Option<Room> {
+        let room_file = self
+            .rooms
+            .get(room_id)
+            .and_then(|handle| self.room_files.get(handle))?;
+        Some(Room::from_file(room_file, room_file.origin))
+    }
+
+    /// Every loaded room reachable from `start` through exits, `start` included.
+    pub fn dungeon(&self, start: &str) -> HashMap<String, Room> {
+        let mut rooms = HashMap::default();
+        let mut frontier = vec![start.to_string()];
+        while let Some(room_id) = frontier.pop() {
+            if rooms.contains_key(&room_id) {
+                continue;
+            }
+            let Some(room) = self.room(&room_id) else {
+                continue;
+            };
+            frontier.extend(room.exits.iter().map(|exit| exit.to.clone()));
+            rooms.insert(room_id, room);
+        }
+        rooms
+    }
+}
+
+/// Rooms reachable through the current room's exits, laid out ahead of time
+/// so walking through one only has to spawn it.
+#[derive(Resource, Default, Debug)]
+pub struct PreparedRooms(pub HashMap<String, Room>);
+
+/// Warns about locked doors a player starting a new game could never get through.
+fn check_dungeon(assets: RoomAssets) {
+    let start = CurrentRoom::default().0;
+    if let Err(unsolvable) = check_dungeon_solvable(&assets.dungeon(&start), &start) {
+        warn!("The dungeon cannot be finished: {}", unsolvable);
+    }
+}
+
+fn build_a_room(mut commands: Commands, assets: RoomAssets, current_room: Res<CurrentRoom>) {
+    let Some(room) = assets.room(&current_room.0) else {
+        warn!("The {} room is not loaded", current_room.0);
+        return;
+    };
+    spawn_room(&mut commands, &assets, &current_room.0, room);
+}
 
+/// Builds `room` with everything placed in it and makes it the room the
+/// players are in.
+pub fn spawn_room(commands: &mut Commands, assets: &RoomAssets, room_id: &str, room: Room) {
     println!("Width: {}, Height: {}", room.width, room.height);
 
-    room.build(commands, &structures);
+    for (character_id, location) in &room.enemy_spawns {
+        let enemy = spawn_enemy(
+            commands,
+            &assets.characters,
+            *character_id,
+            room.get_translation(location.x, location.y) + Vec3::Y,
+            room.patrol_route(*location),
+        );
+        commands.entity(enemy).insert(InRoom);
+    }
+
+    if let Some(catalog) = assets.catalogs.get(&assets.item_cache.catalog) {
+        for placement in &room.items {
+            let (x, y) = placement.at;
+            if !room.is_open(x, y) {
+                println!(
+                    "{} at {},{} is not on an open tile, skipping",
+                    placement.item, x, y
+                );
+                continue;
+            }
+            if let Some(pickup) = spawn_pickup(
+                commands,
+                catalog,
+                &placement.item,
+                placement.count,
+                room.get_translation(x, y) + Vec3::Y * 0.5,
+            ) {
+                commands
+                    .entity(pickup)
+                    .insert((RoomTile(placement.at), InRoom));
+            }
+        }
+    }
+
+    for placement in &room.objects {
+        let (x, y) = placement.at;
+        if !room.is_open(x, y) {
+            println!(
+                "{:?} at {},{} is not on an open tile, skipping",
+                placement.object, x, y
+            );
+            continue;
+        }
+        let transform =
+            Transform::from_translation(room.get_translation(x, y) + Vec3::Y * 0.5).with_rotation(
+                Quat::from_axis_angle(Vec3::Y, placement.facing.to_radians()),
+            );
+        let object = match &placement.object {
+            RoomObject::Chest { loot } => {
+                spawn_chest(commands, &assets.props, loot.clone(), transform)
+            }
+            RoomObject::Lever { channel } => {
+                spawn_lever(commands, &assets.props, *channel, transform)
+            }
+        };
+        commands
+            .entity(object)
+            .insert((RoomTile(placement.at), InRoom));
+    }
+
+    commands.insert_resource(Checkpoint(
+        room.get_translation(room.entry.x, room.entry.y) + Vec3::Y,
+    ));
+    for &(x, y) in &room.checkpoints {
+        if !room.is_open(x, y) {
+            println!("Checkpoint at {},{} is not on an open tile, skipping", x, y);
+            continue;
+        }
+        let checkpoint = spawn_checkpoint(commands, &assets.props, room.get_translation(x, y));
+        commands.entity(checkpoint).insert(InRoom);
+    }
+
+    for exit in &room.exits {
+        let (x, y) = exit.at;
+        let Some(outward) = room.exit_direction(x, y) else {
+            println!(
+                "Exit to {} at {},{} is not a doorway in the outer wall, skipping",
+                exit.to, x, y
+            );
+            continue;
+        };
+        commands.spawn((
+            Name::from(format!("Exit to {}", exit.to)),
+            exit.clone(),
+            InRoom,
+            TransformBundle::from_transform(Transform::from_translation(
+                room.get_translation(x, y) + outward * EXIT_OFFSET + Vec3::Y,
+            )),
+            Collider::cuboid(0.8, 1.0, 0.8),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+        ));
+    }
+
+    let neighbours = room
+        .exits
+        .iter()
+        .filter_map(|exit| {
+            let neighbour = assets.room(&exit.to);
+            if neighbour.is_none() {
+                warn!(
+                    "The {} room leads to {}, which is not loaded",
+                    room_id, exit.to
+                );
+            }
+            Some((exit.to.clone(), neighbour?))
+        })
+        .collect();
+    commands.insert_resource(PreparedRooms(neighbours));
+
+    commands.insert_resource(NavGrid::new(room.clone()));
+    room.build(commands, &assets.structures);
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TileType {
     Wall,
-    Door,
+    Door { locked: bool },
     Empty,
+    Trap(TrapKind),
 }
 
 impl TileType {
     fn is_wall_like(self) -> bool {
-        matches!(self, Self::Wall | Self::Door)
+        matches!(self, Self::Wall | Self::Door { .. })
+    }
+
+    /// Open floor or a trap set into it.
+    pub fn is_floor(self) -> bool {
+        matches!(self, Self::Empty | Self::Trap(_))
     }
 }
 
@@ -139,11 +456,11 @@ impl TileCross {
         // Normal Wall
         if let (Some(east), Some(west)) = (self.east, self.west) {
             if east.is_wall_like() && west.is_wall_like() {
-                if self.south.is_none() || Some(Empty) == self.south {
+                if self.south.is_none() || self.south.map_or(false, TileType::is_floor) {
                     return SouthWall;
                 }
 
-                if self.north.is_none() || Some(Empty) == self.north {
+                if self.north.is_none() || self.north.map_or(false, TileType::is_floor) {
                     return NorthWall;
                 }
             }
@@ -151,11 +468,11 @@ impl TileCross {
 
         if let (Some(north), Some(south)) = (self.north, self.south) {
             if north.is_wall_like() && south.is_wall_like() {
-                if self.west.is_none() || Some(Empty) == self.west {
+                if self.west.is_none() || self.west.map_or(false, TileType::is_floor) {
                     return WestWall;
                 }
 
-                if self.east.is_none() || Some(Empty) == self.east {
+                if self.east.is_none() || self.east.map_or(false, TileType::is_floor) {
                     return EastWall;
                 }
             }
@@ -164,29 +481,30 @@ impl TileCross {
         return NoPart;
     }
 
-    fn door_from_surrounding(self) -> RenderableParts {
+    /// Locked doors get the boarded up doorway until they are opened.
+    fn door_from_surrounding(self, locked: bool) -> RenderableParts {
         use RenderableParts::*;
         use TileType::*;
         if let (Some(east), Some(west)) = (self.east, self.west) {
             if east.is_wall_like() && west.is_wall_like() {
-                if self.south.is_none() || Some(Empty) == self.south {
-                    return SouthDoor;
+                if self.south.is_none() || self.south.map_or(false, TileType::is_floor) {
+                    return if locked { SouthGatedDoor } else { SouthDoor };
                 }
 
-                if self.north.is_none() || Some(Empty) == self.north {
-                    return NorthDoor;
+                if self.north.is_none() || self.north.map_or(false, TileType::is_floor) {
+                    return if locked { NorthGatedDoor } else { NorthDoor };
                 }
             }
         }
 
         if let (Some(north), Some(south)) = (self.north, self.south) {
             if north.is_wall_like() && south.is_wall_like() {
-                if self.west.is_none() || Some(Empty) == self.west {
-                    return WestDoor;
+                if self.west.is_none() || self.west.map_or(false, TileType::is_floor) {
+                    return if locked { WestGatedDoor } else { WestDoor };
                 }
 
-                if self.east.is_none() || Some(Empty) == self.east {
-                    return EastDoor;
+                if self.east.is_none() || self.east.map_or(false, TileType::is_floor) {
+                    return if locked { EastGatedDoor } else { EastDoor };
                 }
             }
         }
@@ -195,7 +513,7 @@ impl TileCross {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Location {
     pub x: usize,
     pub y: usize,
@@ -218,22 +536,63 @@ impl Chamber {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Room {
     pub map: Vec<Tile>,
     width: usize,
     height: usize,
     pub origin: Vec3,
     pub empty_locations: Vec<Location>,
+    pub enemy_spawns: Vec<(CharacterId, Location)>,
+    pub items: Vec<ItemPlacement>,
+    pub doors: Vec<DoorPlacement>,
+    pub objects: Vec<ObjectPlacement>,
+    pub traps: Vec<TrapPlacement>,
+    pub checkpoints: Vec<(usize, usize)>,
+    pub exits: Vec<RoomExit>,
+    pub entry: Location,
 }
 
 impl Room {
+    pub fn from_file(file: &RoomFile, origin: Vec3) -> Room {
+        let mut room = Room {
+            items: file.items.clone(),
+            doors: file.doors.clone(),
+            objects: file.objects.clone(),
+            traps: file.traps.clone(),
+            checkpoints: file.checkpoints.clone(),
+            exits: file.exits.clone(),
+            ..Room::from_str(&file.layout, origin)
+        };
+        if let Some((x, y)) = file.entry {
+            room.entry = Location { x, y };
+        }
+
+        for door in &file.doors {
+            let (x, y) = door.at;
+            let is_door = x < room.width
+                && y < room.height
+                && room
+                    .get(x, y)
+                    .map_or(false, |tile| matches!(tile, TileType::Door { .. }));
+            if !is_door {
+                println!("No doorway at {},{} for a door, skipping", x, y);
+                continue;
+            }
+            if door.key.is_some() {
+                room.set(x, y, Some(TileType::Door { locked: true }));
+            }
+        }
+        room
+    }
+
     fn from_str(input: &str, origin: Vec3) -> Room {
         let mut width: usize = 0;
         let mut height: usize = 0;
         let mut map: Vec<Tile>;
         let mut tile_vec: Vec<(Tile, usize, usize)> = Vec::default();
         let mut empty_locations: Vec<Location> = Vec::new();
+        let mut enemy_spawns: Vec<(CharacterId, Location)> = Vec::new();
         input
             .trim()
             .lines()
@@ -252,8 +611,20 @@ impl Room {
                         if character == 'x' {
                             tile_vec.push((Some(TileType::Wall), x, y));
                         } else if character == 'd' {
-                            tile_vec.push((Some(TileType::Door), x, y));
+                            tile_vec.push((Some(TileType::Door { locked: false }), x, y));
+                        } else if let Some(kind) = TrapKind::from_char(character) {
+                            tile_vec.push((Some(TileType::Trap(kind)), x, y));
                         } else {
+                            let enemy = match character {
+                                'w' => Some(CharacterId::SkeletonWarrior),
+                                'm' => Some(CharacterId::SkeletonMage),
+                                'a' => Some(CharacterId::SkeletonArcher),
+                                'n' => Some(CharacterId::SkeletonMinion),
+                                _ => None,
+                            };
+                            if let Some(character_id) = enemy {
+                                enemy_spawns.push((character_id, Location { x, y }));
+                            }
                             empty_locations.push(Location { x, y });
                             tile_vec.push((Some(TileType::Empty), x, y));
                         }
@@ -266,13 +637,95 @@ impl Room {
             map[(width * y) + x] = t_type;
         }
 
+        let entry = empty_locations
+            .first()
+            .copied()
+            .unwrap_or(Location { x: 0, y: 0 });
+
         Room {
             map,
             width,
             height,
             origin,
+            entry,
             empty_locations,
+            enemy_spawns,
+            items: Vec::new(),
+            doors: Vec::new(),
+            objects: Vec::new(),
+            traps: Vec::new(),
+            checkpoints: Vec::new(),
+            exits: Vec::new(),
+        }
+    }
+
+    /// Which way is out of the room through the doorway at `x`, `y`, if it
+    /// is in the outer wall.
+    fn exit_direction(&self, x: usize, y: usize) -> Option<Vec3> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        if !matches!(self.get(x, y), Some(TileType::Door { .. })) {
+            return None;
+        }
+        if y == 0 {
+            Some(Vec3::NEG_Z)
+        } else if y == self.height - 1 {
+            Some(Vec3::Z)
+        } else if x == 0 {
+            Some(Vec3::NEG_X)
+        } else if x == self.width - 1 {
+            Some(Vec3::X)
+        } else {
+            None
+        }
+    }
+
+    /// The two ends of the open run of tiles around `location` along its row,
+    /// for an enemy to walk back and forth between.
+    pub fn patrol_route(&self, location: Location) -> Vec<Vec3> {
+        let is_open = |x: usize| self.get(x, location.y) == Some(TileType::Empty);
+
+        let mut west = location.x;
+        while west > 0 && is_open(west - 1) {
+            west -= 1;
+        }
+        let mut east = location.x;
+        while east < self.width - 1 && is_open(east + 1) {
+            east += 1;
+        }
+
+        if west == east {
+            return Vec::new();
         }
+        vec![
+            self.get_translation(west, location.y),
+            self.get_translation(east, location.y),
+        ]
+    }
+
+    /// Whether `x`, `y` is inside the room and open floor.
+    pub fn is_open(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.get(x, y) == Some(TileType::Empty)
+    }
+
+    fn trap_trigger(&self, x: usize, y: usize, kind: TrapKind) -> TrapTrigger {
+        self.traps
+            .iter()
+            .find(|trap| trap.at == (x, y))
+            .map_or(kind.default_trigger(), |trap| trap.trigger)
+    }
+
+    pub fn door_at(&self, x: usize, y: usize) -> Option<&DoorPlacement> {
+        self.doors.iter().find(|door| door.at == (x, y))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
     }
 
     pub fn total_tiles(&self) -> usize {
@@ -446,7 +899,7 @@ impl Room {
         )
     }
 
-    fn build(&self, mut commands: Commands, structures: &Res<StructureCache>) {
+    fn build(&self, commands: &mut Commands, structures: &StructureCache) {
         use RenderableParts::*;
         use TileType::*;
 
@@ -458,11 +911,12 @@ impl Room {
                     ..default()
                 },
                 Name::from("Room"),
+                InRoom,
             ))
             .with_children(|parent| {
                 parent.spawn((
                     TransformBundle {
-                        local: Transform::from_translation(self.get_center()),
+                        local: Transform::from_translation(self.get_center() - self.origin),
                         ..default()
                     },
                     Collider::cuboid(self.get_collider_size().x, 0.5, self.get_collider_size().z),
@@ -471,7 +925,7 @@ impl Room {
 
                 parent.spawn((
                     TransformBundle {
-                        local: Transform::from_translation(self.get_center()),
+                        local: Transform::from_translation(self.get_center() - self.origin),
                         ..default()
                     },
                     Collider::cuboid(self.get_collider_size().x, 5.0, self.get_collider_size().z),
@@ -488,42 +942,67 @@ impl Room {
                                 Wall => {
                                     renderable = surrounding.wall_from_surrounding();
                                 }
-                                Door => {
-                                    renderable = surrounding.door_from_surrounding();
+                                Door { locked } => {
+                                    renderable = surrounding.door_from_surrounding(locked);
+                                }
+                                Trap(kind) => {
+                                    spawn_trap(
+                                        parent,
+                                        structures,
+                                        kind,
+                                        self.trap_trigger(x, y, kind),
+                                        self.get_translation(x, y) - self.origin,
+                                    );
                                 }
                                 Empty => (),
                             },
                             None => (),
                         }
+                        let is_door = renderable.is_door();
                         let (handle, rotation, colliders) = renderable.render(structures);
                         if handle != Handle::default() {
-                            parent
-                                .spawn((
-                                    SceneBundle {
-                                        scene: handle,
-                                        transform: Transform::from_translation(
-                                            self.get_translation(x, y),
-                                        )
-                                        .with_rotation(rotation),
-                                        ..default()
-                                    },
-                                    Name::from(format!("Part: {},{}", y, x)),
-                                ))
-                                .with_children(|child| {
-                                    for components in colliders {
-                                        child.spawn((
-                                            TransformBundle {
-                                                local: Transform::from_translation(
-                                                    components.transform.translation
-                                                        + (Vec3::Y * 2.0),
-                                                ),
-                                                ..default()
-                                            },
-                                            components.collider,
-                                            RigidBody::Fixed,
-                                        ));
-                                    }
-                                });
+                            let mut part = parent.spawn((
+                                SceneBundle {
+                                    scene: handle,
+                                    transform: Transform::from_translation(
+                                        self.get_translation(x, y) - self.origin,
+                                    )
+                                    .with_rotation(rotation),
+                                    ..default()
+                                },
+                                Name::from(format!("Part: {},{}", y, x)),
+                            ));
+                            let mut blocker = None;
+                            part.with_children(|child| {
+                                if is_door {
+                                    blocker = Some(child.spawn(DoorBundle::blocker()).id());
+                                }
+                                for components in colliders {
+                                    child.spawn((
+                                        TransformBundle {
+                                            local: Transform::from_translation(
+                                                components.transform.translation + (Vec3::Y * 2.0),
+                                            ),
+                                            ..default()
+                                        },
+                                        components.collider,
+                                        RigidBody::Fixed,
+                                    ));
+                                }
+                            });
+                            if let Some(blocker) = blocker {
+                                let placement = self.door_at(x, y);
+                                let channel = placement.and_then(|door| door.channel);
+                                let key = placement.and_then(|door| door.key.clone());
+                                let locked = key.is_some();
+                                part.insert((
+                                    DoorBundle::new(blocker, channel, key),
+                                    RoomTile((x, y)),
+                                ));
+                                if channel.is_none() || locked {
+                                    part.insert(DoorBundle::interactable(locked));
+                                }
+                            }
                         }
                     }
                 }
@@ -540,6 +1019,10 @@ enum RenderableParts {
     SouthDoor,
     EastDoor,
     WestDoor,
+    NorthGatedDoor,
+    SouthGatedDoor,
+    EastGatedDoor,
+    WestGatedDoor,
     NWestCorner,
     NEastCorner,
     SWestCorner,
@@ -553,7 +1036,21 @@ enum RenderableParts {
 }
 
 impl RenderableParts {
-    fn render(self, structures: &Res<StructureCache>) -> (Handle<Scene>, Quat, Vec<ColliderChild>) {
+    fn is_door(&self) -> bool {
+        matches!(
+            self,
+            Self::NorthDoor
+                | Self::SouthDoor
+                | Self::EastDoor
+                | Self::WestDoor
+                | Self::NorthGatedDoor
+                | Self::SouthGatedDoor
+                | Self::EastGatedDoor
+                | Self::WestGatedDoor
+        )
+    }
+
+    fn render(self, structures: &StructureCache) -> (Handle<Scene>, Quat, Vec<ColliderChild>) {
         match self {
             RenderableParts::NorthWall => (
                 structures.wall.clone_weak(),
@@ -605,6 +1102,26 @@ impl RenderableParts {
                 Quat::from_axis_angle(Vec3::Y, 270.0_f32.to_radians()),
                 Vec::new(),
             ),
+            RenderableParts::NorthGatedDoor => (
+                structures.gated_door.clone_weak(),
+                Quat::default(),
+                Vec::new(),
+            ),
+            RenderableParts::SouthGatedDoor => (
+                structures.gated_door.clone_weak(),
+                Quat::from_axis_angle(Vec3::Y, 180.0_f32.to_radians()),
+                Vec::new(),
+            ),
+            RenderableParts::EastGatedDoor => (
+                structures.gated_door.clone_weak(),
+                Quat::from_axis_angle(Vec3::Y, 90.0_f32.to_radians()),
+                Vec::new(),
+            ),
+            RenderableParts::WestGatedDoor => (
+                structures.gated_door.clone_weak(),
+                Quat::from_axis_angle(Vec3::Y, 270.0_f32.to_radians()),
+                Vec::new(),
+            ),
             RenderableParts::NEastCorner => (
                 structures.wall_corner.clone_weak(),
                 Quat::default(),
@@ -0,0 +1,708 @@
+use crate::combat::Health;
+use crate::interaction::{Chest, Door, Hinge, Interactable, Lever};
+use crate::inventory::{spawn_pickup, Inventory, ItemCatalog, Pickup};
+use crate::physics::Speed;
+use crate::player::{ClassId, Player, PlayerClass, PlayerSlot};
+use crate::room_builder::{CurrentRoom, RoomTile};
+use crate::{GameState, ItemCache};
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use bevy::reflect::serde::{ReflectSerializer, UntypedReflectDeserializer};
+use bevy::reflect::TypeRegistry;
+use bevy::time::Stopwatch;
+use ron::value::RawValue;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Dungeon>()
+            .register_type::<Duration>()
+            .register_type::<Stopwatch>()
+            .register_type::<Timer>()
+            .register_type::<TimerMode>()
+            .add_systems(
+                Update,
+                (
+                    save_game
+                        .run_if(input_just_pressed(KeyCode::F5))
+                        .run_if(in_state(GameState::Gameplay)),
+                    restore_save
+                        .run_if(resource_exists::<LoadedSave>())
+                        .run_if(in_state(GameState::Gameplay)),
+                    restore_waiting_players
+                        .run_if(resource_exists::<WaitingPlayers>())
+                        .run_if(in_state(GameState::Gameplay)),
+                    // Also runs mid-transition, before the next room fades in.
+                    restore_room.run_if(resource_exists::<RestoreRoom>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+pub const SAVE_PATH: &str = "saves/save.ron";
+/// Bumped whenever the layout of `SaveFile` changes, see `migrate`.
+pub const SAVE_VERSION: u32 = 2;
+
+/// The player components written to a save, through reflection. Anything else
+/// found in a save file is ignored.
+fn saved_components() -> [TypeId; 3] {
+    [
+        TypeId::of::<Inventory>(),
+        TypeId::of::<Health>(),
+        TypeId::of::<Speed>(),
+    ]
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveFile {
+    pub version: u32,
+    /// The room the players were in.
+    pub room: String,
+    pub rooms: BTreeMap<String, RoomState>,
+    pub players: Vec<PlayerSave>,
+}
+
+/// What has changed in a room since it was built from its room file.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct RoomState {
+    pub doors: Vec<DoorState>,
+    pub opened_chests: Vec<(usize, usize)>,
+    pub pulled_levers: Vec<(usize, usize)>,
+    /// How much is left of each item placed in the room. Placed items that
+    /// aren't listed have been picked up.
+    pub pickups: Vec<PickupState>,
+    /// Items dropped or spilled somewhere in the room.
+    pub loose: Vec<LoosePickup>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DoorState {
+    pub at: (usize, usize),
+    pub open: bool,
+    pub locked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PickupState {
+    pub at: (usize, usize),
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LoosePickup {
+    pub item: String,
+    pub count: u32,
+    pub position: Vec3,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayerSave {
+    pub slot: usize,
+    pub class: ClassId,
+    pub position: Vec3,
+    /// Which way the player was facing, since version 2.
+    pub rotation: Quat,
+    /// Reflected components from the allow-list, each as its own RON value.
+    pub components: Vec<Box<RawValue>>,
+}
+
+/// Just enough of a save to tell which version wrote it.
+#[derive(Deserialize)]
+#[serde(rename = "SaveFile")]
+struct SaveVersion {
+    version: u32,
+}
+
+/// A save from version 1, before players' facing was kept.
+#[derive(Deserialize)]
+#[serde(rename = "SaveFile")]
+struct SaveFileV1 {
+    room: String,
+    rooms: BTreeMap<String, RoomState>,
+    players: Vec<PlayerSaveV1>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "PlayerSave")]
+struct PlayerSaveV1 {
+    slot: usize,
+    class: ClassId,
+    position: Vec3,
+    components: Vec<Box<RawValue>>,
+}
+
+impl From<SaveFileV1> for SaveFile {
+    fn from(save: SaveFileV1) -> Self {
+        SaveFile {
+            version: 2,
+            room: save.room,
+            rooms: save.rooms,
+            players: save
+                .players
+                .into_iter()
+                .map(|player| PlayerSave {
+                    slot: player.slot,
+                    class: player.class,
+                    position: player.position,
+                    rotation: Quat::IDENTITY,
+                    components: player.components,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(String),
+    /// Written by a newer build of the game.
+    TooNew(u32),
+    /// A version no build of the game ever wrote.
+    UnknownVersion(u32),
+    /// A component that isn't on the allow-list.
+    NotSaved(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{}", error),
+            SaveError::Format(error) => write!(f, "{}", error),
+            SaveError::TooNew(version) => write!(
+                f,
+                "save version {} is newer than this game's {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::UnknownVersion(version) => write!(f, "unknown save version {}", version),
+            SaveError::NotSaved(type_name) => write!(f, "{} is not a saved component", type_name),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(error: std::io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl SaveFile {
+    pub fn exists() -> bool {
+        Path::new(SAVE_PATH).exists()
+    }
+
+    pub fn read() -> Result<SaveFile, SaveError> {
+        SaveFile::from_ron(&fs::read_to_string(SAVE_PATH)?)
+    }
+
+    pub fn write(&self) -> Result<(), SaveError> {
+        let contents = self.to_ron()?;
+        if let Some(directory) = Path::new(SAVE_PATH).parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(SAVE_PATH, contents)?;
+        Ok(())
+    }
+
+    pub fn from_ron(contents: &str) -> Result<SaveFile, SaveError> {
+        let version: SaveVersion = ron::from_str(contents).map_err(format_error)?;
+        migrate(version.version, contents)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| SaveError::Format(error.to_string()))
+    }
+
+    /// The player in the first slot, whose class the menu picks when continuing.
+    pub fn first_player(&self) -> Option<&PlayerSave> {
+        self.players.iter().min_by_key(|player| player.slot)
+    }
+}
+
+fn format_error(error: ron::error::SpannedError) -> SaveError {
+    SaveError::Format(error.to_string())
+}
+
+/// Reads a save written by any version up to this one. When the layout of
+/// `SaveFile` changes, bump `SAVE_VERSION`, keep the old layout as its own
+/// struct and upgrade it here, one version at a time.
+fn migrate(version: u32, contents: &str) -> Result<SaveFile, SaveError> {
+    match version {
+        1 => Ok(ron::from_str::<SaveFileV1>(contents)
+            .map_err(format_error)?
+            .into()),
+        SAVE_VERSION => ron::from_str(contents).map_err(format_error),
+        _ if version > SAVE_VERSION => Err(SaveError::TooNew(version)),
+        _ => Err(SaveError::UnknownVersion(version)),
+    }
+}
+
+/// The state of every room the players have been through, kept for when they
+/// come back and for the next save.
+#[derive(Resource, Default, Debug)]
+pub struct Dungeon {
+    pub rooms: BTreeMap<String, RoomState>,
+}
+
+/// A save picked in the main menu, applied once gameplay starts.
+#[derive(Resource, Debug)]
+pub struct LoadedSave(pub SaveFile);
+
+/// Saved players whose slot nobody has joined yet. A gamepad that joins one of
+/// these slots picks up as the saved player.
+#[derive(Resource, Debug)]
+pub struct WaitingPlayers(pub Vec<PlayerSave>);
+
+impl WaitingPlayers {
+    pub fn class(&self, slot: usize) -> Option<ClassId> {
+        self.0
+            .iter()
+            .find(|player| player.slot == slot)
+            .map(|player| player.class)
+    }
+}
+
+/// Asks for the current room to be put back the way the `Dungeon` remembers it.
+#[derive(Resource, Debug)]
+pub struct RestoreRoom;
+
+pub fn save_game(world: &mut World) {
+    match capture_save(world).and_then(|save| save.write()) {
+        Ok(()) => info!("Saved the game to {}", SAVE_PATH),
+        Err(error) => warn!("Could not save the game: {}", error),
+    }
+}
+
+fn capture_save(world: &mut World) -> Result<SaveFile, SaveError> {
+    let room = world.resource::<CurrentRoom>().0.clone();
+    let state = capture_room(world);
+    let mut dungeon = world.resource_mut::<Dungeon>();
+    dungeon.rooms.insert(room.clone(), state);
+    let rooms = dungeon.rooms.clone();
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let mut player_query =
+        world.query_filtered::<(Entity, &PlayerSlot, &PlayerClass, &Transform), With<Player>>();
+    let mut players = Vec::new();
+    for (entity, slot, class, transform) in player_query.iter(world) {
+        let mut components = Vec::new();
+        for type_id in saved_components() {
+            let Some(component) = registry
+                .get(type_id)
+                .and_then(|registration| registration.data::<ReflectComponent>())
+                .and_then(|reflect_component| reflect_component.reflect(world.entity(entity)))
+            else {
+                continue;
+            };
+            let contents = ron::to_string(&ReflectSerializer::new(component, &registry))
+                .map_err(|error| SaveError::Format(error.to_string()))?;
+            components.push(
+                RawValue::from_boxed_ron(contents.into_boxed_str())
+                    .map_err(|error| SaveError::Format(error.to_string()))?,
+            );
+        }
+        players.push(PlayerSave {
+            slot: slot.0,
+            class: class.0,
+            position: transform.translation,
+            rotation: transform.rotation,
+            components,
+        });
+    }
+
+    Ok(SaveFile {
+        version: SAVE_VERSION,
+        room,
+        rooms,
+        players,
+    })
+}
+
+pub fn capture_room(world: &mut World) -> RoomState {
+    let mut room = RoomState::default();
+
+    let mut door_query = world.query::<(&RoomTile, &Door)>();
+    for (tile, door) in door_query.iter(world) {
+        room.doors.push(DoorState {
+            at: tile.0,
+            open: door.open,
+            locked: door.key.is_some(),
+        });
+    }
+    let mut chest_query = world.query::<(&RoomTile, &Chest)>();
+    for (tile, chest) in chest_query.iter(world) {
+        if chest.opened {
+            room.opened_chests.push(tile.0);
+        }
+    }
+    let mut lever_query = world.query::<(&RoomTile, &Lever)>();
+    for (tile, lever) in lever_query.iter(world) {
+        if lever.on {
+            room.pulled_levers.push(tile.0);
+        }
+    }
+    let mut pickup_query = world.query::<(Option<&RoomTile>, &Pickup, &Transform)>();
+    for (tile, pickup, transform) in pickup_query.iter(world) {
+        match tile {
+            Some(tile) => room.pickups.push(PickupState {
+                at: tile.0,
+                count: pickup.count,
+            }),
+            None => room.loose.push(LoosePickup {
+                item: pickup.item.clone(),
+                count: pickup.count,
+                position: transform.translation,
+            }),
+        }
+    }
+
+    room
+}
+
+/// Hands the saved rooms to the `Dungeon` and puts the saved players back
+/// where they were, with the components they were saved with. Players whose
+/// slot is empty wait for someone to join it.
+fn restore_save(world: &mut World) {
+    let Some(LoadedSave(save)) = world.remove_resource::<LoadedSave>() else {
+        return;
+    };
+    world.insert_resource(Dungeon { rooms: save.rooms });
+    world.insert_resource(RestoreRoom);
+    world.remove_resource::<WaitingPlayers>();
+
+    let mut waiting = Vec::new();
+    for player in save.players {
+        match player_in_slot(world, player.slot) {
+            Some(entity) => restore_player(world, entity, &player),
+            None => waiting.push(player),
+        }
+    }
+    if !waiting.is_empty() {
+        info!(
+            "{} more saved player(s) will be restored when a gamepad joins",
+            waiting.len()
+        );
+        world.insert_resource(WaitingPlayers(waiting));
+    }
+}
+
+fn restore_waiting_players(world: &mut World) {
+    let Some(mut waiting) = world.remove_resource::<WaitingPlayers>() else {
+        return;
+    };
+    waiting
+        .0
+        .retain(|player| match player_in_slot(world, player.slot) {
+            Some(entity) => {
+                restore_player(world, entity, player);
+                false
+            }
+            None => true,
+        });
+    if !waiting.0.is_empty() {
+        world.insert_resource(waiting);
+    }
+}
+
+fn player_in_slot(world: &mut World, slot: usize) -> Option<Entity> {
+    let mut player_query = world.query_filtered::<(Entity, &PlayerSlot), With<Player>>();
+    player_query
+        .iter(world)
+        .find(|(_, player_slot)| player_slot.0 == slot)
+        .map(|(entity, _)| entity)
+}
+
+fn restore_player(world: &mut World, entity: Entity, player: &PlayerSave) {
+    if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+        transform.translation = player.position;
+        transform.rotation = player.rotation;
+    }
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    for component in &player.components {
+        if let Err(error) = restore_component(world, entity, &registry, component) {
+            warn!(
+                "Could not restore a component of player {}: {}",
+                player.slot + 1,
+                error
+            );
+        }
+    }
+}
+
+fn restore_component(
+    world: &mut World,
+    entity: Entity,
+    registry: &TypeRegistry,
+    component: &RawValue,
+) -> Result<(), SaveError> {
+    let mut deserializer = ron::Deserializer::from_str(component.get_ron())
+        .map_err(|error| SaveError::Format(error.to_string()))?;
+    let value = UntypedReflectDeserializer::new(registry)
+        .deserialize(&mut deserializer)
+        .map_err(|error| SaveError::Format(error.to_string()))?;
+
+    let Some(type_info) = value.get_represented_type_info() else {
+        return Err(SaveError::NotSaved(value.type_name().to_string()));
+    };
+    if !saved_components().contains(&type_info.type_id()) {
+        return Err(SaveError::NotSaved(type_info.type_name().to_string()));
+    }
+    let Some(reflect_component) = registry
+        .get(type_info.type_id())
+        .and_then(|registration| registration.data::<ReflectComponent>())
+    else {
+        return Err(SaveError::NotSaved(type_info.type_name().to_string()));
+    };
+    reflect_component.apply_or_insert(&mut world.entity_mut(entity), &*value);
+    Ok(())
+}
+
+/// Opens, unlocks, empties and pulls whatever the `Dungeon` remembers being
+/// changed in the current room.
+#[allow(clippy::type_complexity)]
+fn restore_room(
+    mut commands: Commands,
+    current_room: Res<CurrentRoom>,
+    dungeon: Res<Dungeon>,
+    item_cache: Res<ItemCache>,
+    catalogs: Res<Assets<ItemCatalog>>,
+    mut door_query: Query<
+        (
+            Entity,
+            &RoomTile,
+            &mut Door,
+            &mut Hinge,
+            Option<&mut Interactable>,
+        ),
+        (Without<Chest>, Without<Lever>),
+    >,
+    mut chest_query: Query<(Entity, &RoomTile, &mut Chest, &mut Hinge), Without<Lever>>,
+    mut lever_query: Query<(&RoomTile, &mut Lever, &mut Hinge)>,
+    mut pickup_query: Query<(Entity, &RoomTile, &mut Pickup)>,
+) {
+    commands.remove_resource::<RestoreRoom>();
+    let Some(room) = dungeon.rooms.get(&current_room.0) else {
+        return;
+    };
+
+    for (entity, tile, mut door, mut hinge, interactable) in &mut door_query {
+        let Some(state) = room.doors.iter().find(|door| door.at == tile.0) else {
+            continue;
+        };
+        door.restore(
+            entity,
+            state.open,
+            state.locked,
+            &mut hinge,
+            interactable.map(|interactable| interactable.into_inner()),
+            &mut commands,
+        );
+    }
+
+    for (entity, tile, mut chest, mut hinge) in &mut chest_query {
+        if room.opened_chests.contains(&tile.0) {
+            chest.opened = true;
+            chest.loot.clear();
+            hinge.open = true;
+            commands.entity(entity).remove::<Interactable>();
+        }
+    }
+
+    for (tile, mut lever, mut hinge) in &mut lever_query {
+        lever.on = room.pulled_levers.contains(&tile.0);
+        hinge.open = lever.on;
+    }
+
+    let mut remaining = room.pickups.clone();
+    for (entity, tile, mut pickup) in &mut pickup_query {
+        match remaining.iter().position(|state| state.at == tile.0) {
+            Some(index) => pickup.count = remaining.swap_remove(index).count,
+            None => commands.entity(entity).despawn_recursive(),
+        }
+    }
+
+    if let Some(catalog) = catalogs.get(&item_cache.catalog) {
+        for loose in &room.loose {
+            spawn_pickup(
+                &mut commands,
+                catalog,
+                &loose.item,
+                loose.count,
+                loose.position,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::Momentum;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(SavePlugin)
+            .register_type::<Inventory>()
+            .register_type::<crate::inventory::ItemStack>()
+            .register_type::<Vec<crate::inventory::ItemStack>>()
+            .register_type::<Health>()
+            .register_type::<Speed>()
+            .register_type::<Momentum>()
+            .insert_resource(CurrentRoom("hall".to_string()));
+        app
+    }
+
+    fn spawn_player(
+        world: &mut World,
+        inventory: Inventory,
+        health: Health,
+        speed: Speed,
+    ) -> Entity {
+        world
+            .spawn((
+                Player,
+                PlayerSlot(0),
+                PlayerClass(ClassId::Knight),
+                Transform::default(),
+                inventory,
+                health,
+                speed,
+            ))
+            .id()
+    }
+
+    #[test]
+    fn restores_saved_player_components() {
+        let mut saving = app();
+        let mut inventory = Inventory::default();
+        inventory.add("KEY", 1, 1);
+        inventory.add("POTION", 3, 5);
+        let mut health = Health::new(100.0);
+        health.damage(40.0);
+        let mut speed = Speed::new(3.0, 8.0);
+        speed.set(5.5);
+        let saved = spawn_player(&mut saving.world, inventory, health, speed);
+        let facing = Quat::from_rotation_y(1.0);
+        *saving.world.get_mut::<Transform>(saved).unwrap() =
+            Transform::from_xyz(4.0, 0.0, 8.0).with_rotation(facing);
+
+        let contents = capture_save(&mut saving.world).unwrap().to_ron().unwrap();
+
+        let mut restored = app();
+        let player = spawn_player(
+            &mut restored.world,
+            Inventory::default(),
+            Health::new(100.0),
+            Speed::new(3.0, 8.0),
+        );
+        let save = SaveFile::from_ron(&contents).unwrap();
+        assert_eq!(save.room, "hall");
+        restored.world.insert_resource(LoadedSave(save));
+        restore_save(&mut restored.world);
+
+        let player = restored.world.entity(player);
+        let inventory = player.get::<Inventory>().unwrap();
+        assert_eq!(inventory.count("KEY"), 1);
+        assert_eq!(inventory.count("POTION"), 3);
+        assert_eq!(player.get::<Health>().unwrap().current(), 60.0);
+        assert_eq!(player.get::<Speed>().unwrap().current(), 5.5);
+        let transform = player.get::<Transform>().unwrap();
+        assert_eq!(transform.translation, Vec3::new(4.0, 0.0, 8.0));
+        assert_eq!(transform.rotation, facing);
+        assert!(!restored.world.contains_resource::<WaitingPlayers>());
+    }
+
+    #[test]
+    fn players_without_a_slot_wait_for_it_to_be_joined() {
+        let mut app = app();
+        let contents = "(
+            version: 2,
+            room: \"hall\",
+            rooms: {},
+            players: [(
+                slot: 1,
+                class: Mage,
+                position: (1.0, 0.0, 2.0),
+                rotation: (0.0, 0.0, 0.0, 1.0),
+                components: [],
+            )],
+        )";
+        let save = SaveFile::from_ron(contents).unwrap();
+        app.world.insert_resource(LoadedSave(save));
+        restore_save(&mut app.world);
+        assert_eq!(
+            app.world.resource::<WaitingPlayers>().class(1),
+            Some(ClassId::Mage)
+        );
+
+        let joined = app
+            .world
+            .spawn((Player, PlayerSlot(1), Transform::default()))
+            .id();
+        restore_waiting_players(&mut app.world);
+
+        assert_eq!(
+            app.world.get::<Transform>(joined).unwrap().translation,
+            Vec3::new(1.0, 0.0, 2.0)
+        );
+        assert!(!app.world.contains_resource::<WaitingPlayers>());
+    }
+
+    #[test]
+    fn refuses_components_off_the_allow_list() {
+        let mut app = app();
+        let player = app.world.spawn(Player).id();
+        let registry = app.world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let contents =
+            ron::to_string(&ReflectSerializer::new(&Momentum::default(), &registry)).unwrap();
+        let component = RawValue::from_boxed_ron(contents.into_boxed_str()).unwrap();
+
+        let result = restore_component(&mut app.world, player, &registry, &component);
+
+        assert!(matches!(result, Err(SaveError::NotSaved(_))));
+        assert!(app.world.get::<Momentum>(player).is_none());
+    }
+
+    #[test]
+    fn refuses_saves_from_a_newer_game() {
+        let contents = "(version: 99, room: \"hall\", rooms: {}, players: [])";
+        assert!(matches!(
+            SaveFile::from_ron(contents),
+            Err(SaveError::TooNew(99))
+        ));
+    }
+
+    #[test]
+    fn migrates_version_1_saves() {
+        let contents = "(
+            version: 1,
+            room: \"hall\",
+            rooms: {},
+            players: [(
+                slot: 0,
+                class: Rogue,
+                position: (4.0, 0.0, 8.0),
+                components: [],
+            )],
+        )";
+        let save = SaveFile::from_ron(contents).unwrap();
+
+        assert_eq!(save.version, SAVE_VERSION);
+        assert_eq!(save.players[0].position, Vec3::new(4.0, 0.0, 8.0));
+        assert_eq!(save.players[0].rotation, Quat::IDENTITY);
+    }
+}
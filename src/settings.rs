@@ -0,0 +1,78 @@
+use crate::ron_config;
+use bevy::audio::GlobalVolume;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .add_systems(Update, apply_settings);
+    }
+}
+
+const SETTINGS_PATH: &str = "config/settings.ron";
+
+/// Sound and graphics options, changed from the settings menu. Key bindings
+/// are kept apart in `InputBindings`.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// From 0.0 to 1.0.
+    pub volume: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub anti_aliasing: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 0.8,
+            fullscreen: false,
+            vsync: true,
+            anti_aliasing: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        ron_config::load(SETTINGS_PATH)
+    }
+
+    pub fn save(&self) {
+        ron_config::save(SETTINGS_PATH, self);
+    }
+}
+
+fn apply_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    commands.insert_resource(GlobalVolume::new(settings.volume));
+    commands.insert_resource(if settings.anti_aliasing {
+        Msaa::Sample4
+    } else {
+        Msaa::Off
+    });
+    for mut window in &mut window_query {
+        window.mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+        window.present_mode = if settings.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+    }
+}
@@ -0,0 +1,373 @@
+use crate::room_builder::{Location, Room, RoomObject, TileType};
+use crate::trap::TrapKind;
+use bevy::utils::{HashMap, HashSet};
+use std::fmt;
+
+/// The doors a player reaches but can never get through, with what they need.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Unsolvable {
+    pub doors: Vec<BlockedDoor>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BlockedDoor {
+    /// Which room of the dungeon the door is in, `None` when checking a room on its own.
+    pub room: Option<String>,
+    pub at: Location,
+    pub requirement: DoorRequirement,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DoorRequirement {
+    Key(String),
+    Lever(u32),
+}
+
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, door) in self.doors.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "door at {},{}", door.at.x, door.at.y)?;
+            if let Some(room) = &door.room {
+                write!(f, " in {}", room)?;
+            }
+            match &door.requirement {
+                DoorRequirement::Key(key) => write!(f, " needs a {} that is out of reach", key)?,
+                DoorRequirement::Lever(channel) => write!(
+                    f,
+                    " needs a lever on channel {} that is out of reach",
+                    channel
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Room {
+    /// Checks the room on its own, see `check_dungeon_solvable`. Exits are
+    /// never followed.
+    pub fn check_solvable(&self) -> Result<(), Unsolvable> {
+        walk(&[(None, self)])
+    }
+}
+
+/// Walks the dungeon from the entry of the `start` room the way a player
+/// would, picking up items from the floor and from chests, unlocking doors once
+/// their key is carried and opening lever doors once a lever on their channel
+/// has been reached in the same room. Keys are spent on the first locked door
+/// they fit, like in game, and are carried from room to room through exits.
+///
+/// Fails with every door that was reached but could not be opened.
+pub fn check_dungeon_solvable(
+    rooms: &HashMap<String, Room>,
+    start: &str,
+) -> Result<(), Unsolvable> {
+    let Some(first) = rooms.get(start) else {
+        return Ok(());
+    };
+    let mut walked = vec![(Some(start), first)];
+    walked.extend(
+        rooms
+            .iter()
+            .filter(|(id, _)| id.as_str() != start)
+            .map(|(id, room)| (Some(id.as_str()), room)),
+    );
+    walk(&walked)
+}
+
+/// A tile in one of the walked rooms, by its index in them.
+type Place = (usize, Location);
+
+fn walk(rooms: &[(Option<&str>, &Room)]) -> Result<(), Unsolvable> {
+    let room_index = |id: &str| rooms.iter().position(|(room_id, _)| *room_id == Some(id));
+
+    let mut visited: Vec<Vec<bool>> = rooms
+        .iter()
+        .map(|(_, room)| vec![false; room.total_tiles()])
+        .collect();
+    let mut carried: HashMap<&str, u32> = HashMap::default();
+    let mut levers: HashSet<(usize, u32)> = HashSet::default();
+    let mut unlocked: HashSet<(usize, (usize, usize))> = HashSet::default();
+    let mut opened: HashSet<(usize, (usize, usize))> = HashSet::default();
+    let mut blocked: Vec<Place> = Vec::new();
+    let mut frontier: Vec<Place> = vec![(0, rooms[0].1.entry)];
+
+    loop {
+        while let Some((current, location)) = frontier.pop() {
+            let room = rooms[current].1;
+            let (x, y) = (location.x, location.y);
+            if x >= room.width() || y >= room.height() || visited[current][y * room.width() + x] {
+                continue;
+            }
+            match room.get(x, y) {
+                // Spikes hurt and grates open under whoever lingers, but both
+                // can be run across. Pits only drop you back where you came from.
+                Some(TileType::Empty)
+                | Some(TileType::Trap(TrapKind::Spikes))
+                | Some(TileType::Trap(TrapKind::Grate)) => (),
+                Some(TileType::Door { .. }) => {
+                    if room
+                        .door_at(x, y)
+                        .map_or(false, |door| door.key.is_some() || door.channel.is_some())
+                        && !opened.contains(&(current, (x, y)))
+                    {
+                        if !blocked.contains(&(current, location)) {
+                            blocked.push((current, location));
+                        }
+                        continue;
+                    }
+                }
+                _ => continue,
+            }
+            visited[current][y * room.width() + x] = true;
+
+            for item in room.items.iter().filter(|item| item.at == (x, y)) {
+                *carried.entry(item.item.as_str()).or_default() += item.count;
+            }
+            for placement in room.objects.iter().filter(|object| object.at == (x, y)) {
+                match &placement.object {
+                    RoomObject::Chest { loot } => {
+                        for stack in loot {
+                            *carried.entry(stack.item.as_str()).or_default() += stack.count;
+                        }
+                    }
+                    RoomObject::Lever { channel } => {
+                        levers.insert((current, *channel));
+                    }
+                }
+            }
+            for exit in room.exits.iter().filter(|exit| exit.at == (x, y)) {
+                let Some(next) = room_index(&exit.to) else {
+                    continue;
+                };
+                let entry = exit
+                    .entry
+                    .map_or(rooms[next].1.entry, |(x, y)| Location { x, y });
+                frontier.push((next, entry));
+            }
+
+            frontier.push((current, Location { x: x + 1, y }));
+            frontier.push((current, Location { x, y: y + 1 }));
+            if let Some(x) = x.checked_sub(1) {
+                frontier.push((current, Location { x, y }));
+            }
+            if let Some(y) = y.checked_sub(1) {
+                frontier.push((current, Location { x, y }));
+            }
+        }
+
+        let mut progressed = false;
+        blocked.retain(|(current, location)| {
+            let Some(door) = rooms[*current].1.door_at(location.x, location.y) else {
+                return false;
+            };
+            if let Some(key) = &door.key {
+                if !unlocked.contains(&(*current, door.at)) {
+                    match carried.get_mut(key.as_str()) {
+                        Some(count) if *count > 0 => {
+                            *count -= 1;
+                            unlocked.insert((*current, door.at));
+                        }
+                        _ => return true,
+                    }
+                }
+            }
+            let can_open = door
+                .channel
+                .map_or(true, |channel| levers.contains(&(*current, channel)));
+            if can_open {
+                opened.insert((*current, door.at));
+                frontier.push((*current, *location));
+                progressed = true;
+            }
+            !can_open
+        });
+
+        if !progressed {
+            break;
+        }
+    }
+
+    if blocked.is_empty() {
+        return Ok(());
+    }
+    Err(Unsolvable {
+        doors: blocked
+            .into_iter()
+            .filter_map(|(current, location)| {
+                let (room_id, room) = rooms[current];
+                let door = room.door_at(location.x, location.y)?;
+                let requirement = match (&door.key, door.channel) {
+                    (Some(key), _) if !unlocked.contains(&(current, door.at)) => {
+                        DoorRequirement::Key(key.clone())
+                    }
+                    (_, Some(channel)) => DoorRequirement::Lever(channel),
+                    _ => return None,
+                };
+                Some(BlockedDoor {
+                    room: room_id.map(str::to_string),
+                    at: location,
+                    requirement,
+                })
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_builder::RoomFile;
+    use bevy::prelude::Vec3;
+
+    /// A room from the body of a `room.ron` file, after its layout.
+    fn room(layout: &str, placements: &str) -> Room {
+        let file: RoomFile =
+            ron::from_str(&format!("(layout: {:?}, {})", layout, placements)).unwrap();
+        Room::from_file(&file, Vec3::ZERO)
+    }
+
+    const CORRIDOR: &str = "
+        xxxxxxx
+        x  d  x
+        xxxxxxx
+    ";
+
+    fn blocked(result: Result<(), Unsolvable>) -> Vec<(usize, usize, DoorRequirement)> {
+        result
+            .unwrap_err()
+            .doors
+            .into_iter()
+            .map(|door| (door.at.x, door.at.y, door.requirement))
+            .collect()
+    }
+
+    #[test]
+    fn key_behind_its_own_door_is_unsolvable() {
+        let room = room(
+            CORRIDOR,
+            r#"doors: [(at: (3, 1), key: Some("key"))], items: [(item: "key", at: (5, 1))]"#,
+        );
+        assert_eq!(
+            blocked(room.check_solvable()),
+            vec![(3, 1, DoorRequirement::Key("key".to_string()))]
+        );
+    }
+
+    #[test]
+    fn key_before_its_door_is_solvable() {
+        let room = room(
+            CORRIDOR,
+            r#"doors: [(at: (3, 1), key: Some("key"))], items: [(item: "key", at: (2, 1))]"#,
+        );
+        assert_eq!(room.check_solvable(), Ok(()));
+    }
+
+    #[test]
+    fn key_in_a_chest_is_solvable() {
+        let room = room(
+            CORRIDOR,
+            r#"
+            doors: [(at: (3, 1), key: Some("key"))],
+            objects: [(object: Chest(loot: [(item: "key", count: 1)]), at: (2, 1))],
+            "#,
+        );
+        assert_eq!(room.check_solvable(), Ok(()));
+    }
+
+    #[test]
+    fn lever_opens_its_door() {
+        let room = room(
+            CORRIDOR,
+            r#"
+            doors: [(at: (3, 1), channel: Some(1))],
+            objects: [(object: Lever(channel: 1), at: (1, 1))],
+            "#,
+        );
+        assert_eq!(room.check_solvable(), Ok(()));
+    }
+
+    #[test]
+    fn lever_behind_its_door_is_unsolvable() {
+        let room = room(
+            CORRIDOR,
+            r#"
+            doors: [(at: (3, 1), channel: Some(1))],
+            objects: [(object: Lever(channel: 1), at: (5, 1))],
+            "#,
+        );
+        assert_eq!(
+            blocked(room.check_solvable()),
+            vec![(3, 1, DoorRequirement::Lever(1))]
+        );
+    }
+
+    #[test]
+    fn one_key_only_opens_one_door() {
+        let room = room(
+            "
+            xxxxxxxxx
+            x  d  d x
+            xxxxxxxxx
+            ",
+            r#"
+            doors: [(at: (3, 1), key: Some("key")), (at: (6, 1), key: Some("key"))],
+            items: [(item: "key", at: (2, 1))],
+            "#,
+        );
+        assert_eq!(
+            blocked(room.check_solvable()),
+            vec![(6, 1, DoorRequirement::Key("key".to_string()))]
+        );
+    }
+
+    /// A corridor with a locked door, and an exit below its entry to `to`.
+    fn hall(to: &str) -> Room {
+        room(
+            "
+            xxxxxxx
+            x  d  x
+            xdxxxxx
+            ",
+            &format!(
+                r#"doors: [(at: (3, 1), key: Some("key"))], exits: [(at: (1, 2), to: {:?})]"#,
+                to
+            ),
+        )
+    }
+
+    #[test]
+    fn key_from_another_room_opens_the_door() {
+        let store = room(
+            "
+            xxxx
+            x  x
+            xdxx
+            ",
+            r#"items: [(item: "key", at: (2, 1))], exits: [(at: (1, 2), to: "hall")]"#,
+        );
+        let rooms = HashMap::from_iter([
+            ("hall".to_string(), hall("store")),
+            ("store".to_string(), store),
+        ]);
+
+        assert!(rooms["hall"].check_solvable().is_err());
+        assert_eq!(check_dungeon_solvable(&rooms, "hall"), Ok(()));
+    }
+
+    #[test]
+    fn blocked_doors_name_their_room() {
+        let rooms = HashMap::from_iter([("hall".to_string(), hall("nowhere"))]);
+
+        let unsolvable = check_dungeon_solvable(&rooms, "hall").unwrap_err();
+        assert_eq!(unsolvable.doors.len(), 1);
+        assert_eq!(unsolvable.doors[0].room.as_deref(), Some("hall"));
+        assert_eq!(
+            unsolvable.to_string(),
+            "door at 3,1 in hall needs a key that is out of reach"
+        );
+    }
+}
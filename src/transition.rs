@@ -0,0 +1,189 @@
+use crate::camera::RoomTransitionEvent;
+use crate::combat::Dead;
+use crate::enemy::Debris;
+use crate::fade::ScreenFade;
+use crate::inventory::Pickup;
+use crate::player::{Checkpoint, Player};
+use crate::room_builder::{
+    spawn_room, CurrentRoom, InRoom, Location, PreparedRooms, RoomAssets, RoomExit,
+};
+use crate::save::{capture_room, Dungeon, RestoreRoom};
+use crate::trap::SafeGround;
+use crate::GameState;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, take_exits.run_if(in_state(GameState::Gameplay)))
+            .add_systems(OnEnter(GameState::Transition), start_transition)
+            .add_systems(
+                Update,
+                (
+                    leave_room.run_if(in_stage(TransitionStage::Leaving)),
+                    enter_room.run_if(in_stage(TransitionStage::Entering)),
+                    advance_transition,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Transition)),
+            )
+            .add_systems(OnExit(GameState::Transition), finish_transition);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TransitionStage {
+    FadingOut,
+    Leaving,
+    Entering,
+    FadingIn,
+}
+
+/// The move to another room going on during `GameState::Transition`.
+#[derive(Resource, Debug)]
+pub struct RoomTransition {
+    pub to: String,
+    pub entry: Option<(usize, usize)>,
+    stage: TransitionStage,
+}
+
+fn in_stage(stage: TransitionStage) -> impl Fn(Option<Res<RoomTransition>>) -> bool {
+    move |transition: Option<Res<RoomTransition>>| {
+        transition.map_or(false, |transition| transition.stage == stage)
+    }
+}
+
+fn take_exits(
+    mut commands: Commands,
+    mut collision_reader: EventReader<CollisionEvent>,
+    exit_query: Query<&RoomExit>,
+    player_query: Query<(), (With<Player>, Without<Dead>)>,
+    prepared: Res<PreparedRooms>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in collision_reader.iter() {
+        let CollisionEvent::Started(first, second, _) = event else {
+            continue;
+        };
+        for (sensor, player) in [(*first, *second), (*second, *first)] {
+            let Ok(exit) = exit_query.get(sensor) else {
+                continue;
+            };
+            if !player_query.contains(player) {
+                continue;
+            }
+            // Neighbours that failed to load were already warned about.
+            if !prepared.0.contains_key(&exit.to) {
+                continue;
+            }
+            commands.insert_resource(RoomTransition {
+                to: exit.to.clone(),
+                entry: exit.entry,
+                stage: TransitionStage::FadingOut,
+            });
+            next_state.set(GameState::Transition);
+            return;
+        }
+    }
+}
+
+/// Gameplay systems only run in `GameState::Gameplay`, so they already wait
+/// out the transition. Physics has to be told to.
+fn start_transition(mut fade: ResMut<ScreenFade>, mut rapier_config: ResMut<RapierConfiguration>) {
+    fade.fade_out();
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn finish_transition(mut commands: Commands, mut rapier_config: ResMut<RapierConfiguration>) {
+    commands.remove_resource::<RoomTransition>();
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn advance_transition(
+    fade: Res<ScreenFade>,
+    transition: Option<ResMut<RoomTransition>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut transition) = transition else {
+        next_state.set(GameState::Gameplay);
+        return;
+    };
+    match transition.stage {
+        TransitionStage::FadingOut if fade.is_black() => {
+            transition.stage = TransitionStage::Leaving;
+        }
+        TransitionStage::FadingIn if fade.is_clear() => {
+            next_state.set(GameState::Gameplay);
+        }
+        _ => (),
+    }
+}
+
+/// Remembers how the players left the room, then clears it away along with
+/// anything dropped or broken in it.
+fn leave_room(world: &mut World) {
+    let room = world.resource::<CurrentRoom>().0.clone();
+    let state = capture_room(world);
+    world.resource_mut::<Dungeon>().rooms.insert(room, state);
+
+    let mut room_query =
+        world.query_filtered::<Entity, Or<(With<InRoom>, With<Pickup>, With<Debris>)>>();
+    let entities: Vec<Entity> = room_query.iter(world).collect();
+    for entity in entities {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    let mut transition = world.resource_mut::<RoomTransition>();
+    transition.stage = TransitionStage::Entering;
+    let to = transition.to.clone();
+    world.insert_resource(CurrentRoom(to));
+}
+
+/// Builds the next room and brings the players in at its entry.
+fn enter_room(
+    mut commands: Commands,
+    assets: RoomAssets,
+    current_room: Res<CurrentRoom>,
+    mut prepared: ResMut<PreparedRooms>,
+    mut transition: ResMut<RoomTransition>,
+    mut fade: ResMut<ScreenFade>,
+    mut transition_writer: EventWriter<RoomTransitionEvent>,
+    mut player_query: Query<(Entity, &mut Transform, Option<&mut Velocity>), With<Player>>,
+) {
+    transition.stage = TransitionStage::FadingIn;
+    fade.fade_in();
+
+    let Some(room) = prepared
+        .0
+        .remove(&current_room.0)
+        .or_else(|| assets.room(&current_room.0))
+    else {
+        warn!("The {} room is not loaded", current_room.0);
+        return;
+    };
+
+    let entry = transition
+        .entry
+        .map(|(x, y)| Location { x, y })
+        .unwrap_or(room.entry);
+    let arrival = room.get_translation(entry.x, entry.y) + Vec3::Y;
+    for (entity, mut transform, velocity) in &mut player_query {
+        transform.translation = arrival;
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::zero();
+        }
+        commands.entity(entity).insert(SafeGround(arrival));
+    }
+
+    spawn_room(&mut commands, &assets, &current_room.0, room);
+    // Dying in here brings players back to where they came in.
+    commands.insert_resource(Checkpoint(arrival));
+    commands.insert_resource(RestoreRoom);
+    transition_writer.send(RoomTransitionEvent {
+        target_transform: Transform::from_translation(arrival),
+    });
+}
@@ -0,0 +1,379 @@
+use crate::combat::{DamageEvent, Health};
+use crate::interaction::find_part;
+use crate::navigation::NavGrid;
+use crate::room_builder::TileType;
+use crate::{GameState, StructureCache};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+pub struct TrapPlugin;
+
+impl Plugin for TrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                track_trap_occupants,
+                run_trap_triggers,
+                spring_traps,
+                raise_spikes,
+                swap_grates,
+                track_safe_ground,
+                land_falls,
+            )
+                .chain()
+                .run_if(in_state(GameState::Gameplay)),
+        );
+    }
+}
+
+const SPIKE_DAMAGE: f32 = 15.0;
+const SPIKE_KNOCKBACK: f32 = 4.0;
+/// How far below the floor retracted spikes sit, and how fast they move.
+const SPIKES_HIDDEN: f32 = -2.0;
+const SPIKE_SPEED: f32 = 12.0;
+const FALL_DAMAGE: f32 = 10.0;
+const FALL_SECONDS: f32 = 1.0;
+
+/// `^` spikes, `#` a grate over a pit and `o` an open pit in a room layout.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrapKind {
+    Spikes,
+    Grate,
+    Pit,
+}
+
+impl TrapKind {
+    pub fn from_char(character: char) -> Option<Self> {
+        match character {
+            '^' => Some(TrapKind::Spikes),
+            '#' => Some(TrapKind::Grate),
+            'o' => Some(TrapKind::Pit),
+            _ => None,
+        }
+    }
+
+    pub fn default_trigger(self) -> TrapTrigger {
+        match self {
+            TrapKind::Spikes => TrapTrigger::Periodic {
+                interval: 3.0,
+                active_for: 1.0,
+            },
+            TrapKind::Grate => TrapTrigger::PressurePlate {
+                delay: 0.6,
+                active_for: 3.0,
+            },
+            TrapKind::Pit => TrapTrigger::Always,
+        }
+    }
+
+    fn scene(self, structures: &StructureCache) -> Handle<Scene> {
+        match self {
+            TrapKind::Spikes => structures.spikes.clone_weak(),
+            TrapKind::Grate => structures.grate.clone_weak(),
+            TrapKind::Pit => structures.pit.clone_weak(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TrapTrigger {
+    Always,
+    /// Springs for the last `active_for` seconds of every `interval`.
+    Periodic {
+        interval: f32,
+        active_for: f32,
+    },
+    /// Springs `delay` seconds after someone steps on it, for `active_for` seconds.
+    PressurePlate {
+        delay: f32,
+        active_for: f32,
+    },
+}
+
+#[derive(Component, Debug)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub trigger: TrapTrigger,
+    pub active: bool,
+    clock: f32,
+    /// Characters standing in the trap's sensor.
+    occupants: Vec<Entity>,
+    /// Characters already hurt this time the trap sprung.
+    hit: Vec<Entity>,
+}
+
+/// The sensor child of a trap tile.
+#[derive(Component)]
+struct TrapSensor;
+
+/// The last open floor a character stood on, where they come back after a fall.
+#[derive(Component, Debug)]
+pub struct SafeGround(pub Vec3);
+
+/// A character dropping through a pit, with their collider switched off.
+#[derive(Component, Debug)]
+pub struct Falling {
+    timer: Timer,
+}
+
+pub fn spawn_trap(
+    parent: &mut ChildBuilder,
+    structures: &StructureCache,
+    kind: TrapKind,
+    trigger: TrapTrigger,
+    translation: Vec3,
+) {
+    // The grate models only cover half a tile.
+    let scale = match kind {
+        TrapKind::Grate => Vec3::new(1.0, 1.0, 2.0),
+        _ => Vec3::ONE,
+    };
+
+    parent
+        .spawn((
+            Name::from(format!("Trap: {:?}", kind)),
+            Trap {
+                kind,
+                trigger,
+                active: false,
+                clock: 0.0,
+                occupants: Vec::new(),
+                hit: Vec::new(),
+            },
+            SceneBundle {
+                scene: kind.scene(structures),
+                transform: Transform::from_translation(translation).with_scale(scale),
+                ..default()
+            },
+        ))
+        .with_children(|trap| {
+            trap.spawn((
+                TrapSensor,
+                TransformBundle::from_transform(Transform::from_xyz(0.0, 1.0, 0.0)),
+                Collider::cuboid(1.6, 0.6, 1.6 / scale.z),
+                Sensor,
+                ActiveEvents::COLLISION_EVENTS,
+            ));
+        });
+}
+
+fn track_trap_occupants(
+    mut collision_reader: EventReader<CollisionEvent>,
+    sensor_query: Query<&Parent, With<TrapSensor>>,
+    character_query: Query<(), With<Health>>,
+    mut trap_query: Query<&mut Trap>,
+) {
+    for event in collision_reader.iter() {
+        let (first, second, entered) = match event {
+            CollisionEvent::Started(first, second, _) => (*first, *second, true),
+            CollisionEvent::Stopped(first, second, _) => (*first, *second, false),
+        };
+
+        for (sensor, character) in [(first, second), (second, first)] {
+            if !character_query.contains(character) {
+                continue;
+            }
+            let Ok(mut trap) = sensor_query
+                .get(sensor)
+                .and_then(|parent| trap_query.get_mut(parent.get()))
+            else {
+                continue;
+            };
+            trap.occupants.retain(|entity| *entity != character);
+            if entered {
+                trap.occupants.push(character);
+            }
+        }
+    }
+}
+
+fn run_trap_triggers(time: Res<Time>, mut trap_query: Query<&mut Trap>) {
+    for mut trap in &mut trap_query {
+        let was_active = trap.active;
+        match trap.trigger {
+            TrapTrigger::Always => trap.active = true,
+            TrapTrigger::Periodic {
+                interval,
+                active_for,
+            } => {
+                trap.clock = (trap.clock + time.delta_seconds()) % interval.max(f32::EPSILON);
+                trap.active = trap.clock >= interval - active_for;
+            }
+            TrapTrigger::PressurePlate { delay, active_for } => {
+                if trap.clock > 0.0 || !trap.occupants.is_empty() {
+                    trap.clock += time.delta_seconds();
+                }
+                trap.active = trap.clock >= delay;
+                if trap.clock >= delay + active_for {
+                    trap.clock = 0.0;
+                    trap.active = false;
+                }
+            }
+        }
+
+        if was_active && !trap.active {
+            trap.hit.clear();
+        }
+    }
+}
+
+fn spring_traps(
+    mut commands: Commands,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut trap_query: Query<(Entity, &mut Trap)>,
+    falling_query: Query<(), With<Falling>>,
+) {
+    for (entity, mut trap) in &mut trap_query {
+        if !trap.active {
+            continue;
+        }
+
+        match trap.kind {
+            TrapKind::Spikes => {
+                let Trap { occupants, hit, .. } = &mut *trap;
+                for occupant in occupants.iter() {
+                    if hit.contains(occupant) {
+                        continue;
+                    }
+                    hit.push(*occupant);
+                    damage_writer.send(DamageEvent {
+                        target: *occupant,
+                        source: entity,
+                        amount: SPIKE_DAMAGE,
+                        knockback: Vec3::Y * SPIKE_KNOCKBACK,
+                    });
+                }
+            }
+            TrapKind::Grate | TrapKind::Pit => {
+                for occupant in trap.occupants.drain(..) {
+                    if falling_query.contains(occupant) {
+                        continue;
+                    }
+                    commands.entity(occupant).insert((
+                        Falling {
+                            timer: Timer::from_seconds(FALL_SECONDS, TimerMode::Once),
+                        },
+                        ColliderDisabled,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn raise_spikes(
+    time: Res<Time>,
+    trap_query: Query<(Entity, &Trap)>,
+    children_query: Query<&Children>,
+    name_query: Query<&Name>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    for (entity, trap) in &trap_query {
+        if trap.kind != TrapKind::Spikes {
+            continue;
+        }
+        // The tile's own node also ends in `spikes`, the blades are its child.
+        let Some(spikes) = find_part(entity, "spikes", &children_query, &name_query)
+            .and_then(|tile| find_part(tile, "spikes", &children_query, &name_query))
+        else {
+            continue;
+        };
+        let Ok(mut transform) = transform_query.get_mut(spikes) else {
+            continue;
+        };
+
+        let target = if trap.active { 0.0 } else { SPIKES_HIDDEN };
+        let step = SPIKE_SPEED * time.delta_seconds();
+        let offset = target - transform.translation.y;
+        transform.translation.y += offset.clamp(-step, step);
+    }
+}
+
+fn swap_grates(
+    structures: Res<StructureCache>,
+    mut trap_query: Query<(&Trap, &mut Handle<Scene>), Changed<Trap>>,
+) {
+    for (trap, mut scene) in &mut trap_query {
+        if trap.kind != TrapKind::Grate {
+            continue;
+        }
+        let wanted = if trap.active {
+            &structures.grate_open
+        } else {
+            &structures.grate
+        };
+        if *scene != *wanted {
+            *scene = wanted.clone_weak();
+        }
+    }
+}
+
+fn track_safe_ground(
+    mut commands: Commands,
+    nav_grid: Option<Res<NavGrid>>,
+    mut character_query: Query<
+        (Entity, &Transform, Option<&mut SafeGround>),
+        (With<Health>, Without<Falling>),
+    >,
+) {
+    let Some(nav_grid) = nav_grid else {
+        return;
+    };
+
+    for (entity, transform, safe_ground) in &mut character_query {
+        let safe = matches!(
+            nav_grid.tile_at(transform.translation),
+            Some(TileType::Empty) | Some(TileType::Door { .. })
+        );
+        if !safe {
+            continue;
+        }
+        match safe_ground {
+            Some(mut safe_ground) => safe_ground.0 = transform.translation,
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(SafeGround(transform.translation));
+            }
+        }
+    }
+}
+
+/// Brings fallen characters back up at their last safe ground, a little worse for wear.
+fn land_falls(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut falling_query: Query<(
+        Entity,
+        &mut Falling,
+        &mut Transform,
+        Option<&mut Velocity>,
+        Option<&SafeGround>,
+    )>,
+) {
+    for (entity, mut falling, mut transform, velocity, safe_ground) in &mut falling_query {
+        falling.timer.tick(time.delta());
+        if !falling.timer.finished() {
+            continue;
+        }
+
+        if let Some(safe_ground) = safe_ground {
+            transform.translation = safe_ground.0;
+        }
+        if let Some(mut velocity) = velocity {
+            *velocity = Velocity::zero();
+        }
+        commands
+            .entity(entity)
+            .remove::<(Falling, ColliderDisabled)>();
+        damage_writer.send(DamageEvent {
+            target: entity,
+            source: entity,
+            amount: FALL_DAMAGE,
+            knockback: Vec3::ZERO,
+        });
+    }
+}